@@ -0,0 +1,70 @@
+// clock.rs
+use std::fmt::Debug;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration as StdDuration, Instant};
+use chrono::{DateTime, Duration, Local, Utc};
+use futures::future::BoxFuture;
+
+/// Source of "now" for everything time dependent, so the snipe timing can run against a fake time.
+pub trait Clock: Debug + Send + Sync {
+    fn now_local(&self) -> DateTime<Local>;
+    fn now_utc(&self) -> DateTime<Utc>;
+
+    /// Waits out `duration` of this clock's time. A clock that doesn't run in real time can
+    /// override this to advance itself instead, so a whole countdown replays instantly.
+    fn sleep(&self, duration: StdDuration) -> BoxFuture<'static, ()> {
+        Box::pin(tokio::time::sleep(duration))
+    }
+}
+
+/// The machine's clock.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now_local(&self) -> DateTime<Local> {
+        Local::now()
+    }
+
+    fn now_utc(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+}
+
+/// A clock that starts at a chosen time and then runs at real speed, except that sleeping on it
+/// jumps straight ahead, so a snipe counts down and fires without waiting for the real drop.
+#[derive(Debug, Clone)]
+pub struct SimulatedClock {
+    start: DateTime<Utc>,
+    started: Instant,
+    // total time slept, shared by clones so every holder sees the same jumps
+    skipped: Arc<Mutex<StdDuration>>,
+}
+
+impl SimulatedClock {
+    pub fn starting_at(start: DateTime<Utc>) -> Self {
+        SimulatedClock { start, started: Instant::now(), skipped: Arc::default() }
+    }
+
+    fn skipped(&self) -> StdDuration {
+        self.skipped.lock().map(|skipped| *skipped).unwrap_or_default()
+    }
+}
+
+impl Clock for SimulatedClock {
+    fn now_local(&self) -> DateTime<Local> {
+        self.now_utc().with_timezone(&Local)
+    }
+
+    fn now_utc(&self) -> DateTime<Utc> {
+        self.start + Duration::from_std(self.started.elapsed() + self.skipped()).unwrap_or_else(|_| Duration::zero())
+    }
+
+    fn sleep(&self, duration: StdDuration) -> BoxFuture<'static, ()> {
+        if let Ok(mut skipped) = self.skipped.lock() {
+            *skipped += duration;
+        }
+        // still yield, so a countdown loop lets other tasks run
+        Box::pin(tokio::task::yield_now())
+    }
+}
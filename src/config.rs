@@ -1,12 +1,24 @@
+use std::collections::HashMap;
 use std::fs;
 use std::fs::OpenOptions;
 use std::path::{Path, PathBuf};
-use anyhow::{Context, Result};
+use std::str::FromStr;
+use anyhow::{anyhow, Context, Result};
 use serde::{Serialize, Deserialize};
 use toml;
-use chrono::{Utc, Duration, Local};
+use chrono::{Duration, NaiveDate};
+use crate::clock::{Clock, SystemClock};
+use crate::resy_api_gateway::BookBodyFormat;
+use crate::resy_client::parse_hhmm;
 
 
+/// Keys accepted by `marksman set`; `venue-url` is resolved by the client, the rest by [`Config::set`].
+pub const SETTABLE_KEYS: &[&str] = &[
+    "venue-url", "date", "party-size", "target-time", "earliest-time", "latest-time",
+    "snipe-time", "snipe-date", "seating", "fill-preference", "location", "venue-timezone",
+    "webhook-url", "max-retries", "log-level", "notifications", "payment-preference",
+];
+
 #[derive(Serialize, Deserialize, Debug)]
 pub struct Config {
     #[serde(default)]
@@ -21,6 +33,10 @@ pub struct Config {
     #[serde(default)]
     pub venue_slug: String,
 
+    pub venue_name: Option<String>,
+
+    pub venue_neighborhood: Option<String>,
+
     #[serde(default = "_default_date")]
     pub date: String,
 
@@ -36,38 +52,285 @@ pub struct Config {
     pub snipe_date: String,
 
     #[serde(default)]
-    pub payment_id: String
+    pub payment_id: String,
+
+    #[serde(default)]
+    pub fill_preference: Option<FillPreference>,
+
+    #[serde(default)]
+    pub profiles: HashMap<String, VenueProfile>,
+
+    #[serde(default)]
+    pub active_profile: Option<String>,
+
+    #[serde(default = "_default_max_retries")]
+    pub max_retries: u32,
+
+    #[serde(default)]
+    pub seating_preference: Option<String>,
+
+    #[serde(default)]
+    pub last_booking: Option<BookingRecord>,
+
+    pub earliest_time: Option<String>,
+
+    pub latest_time: Option<String>,
+
+    #[serde(default = "_default_ntp_server")]
+    pub ntp_server: String,
+
+    #[serde(default)]
+    pub notifications_enabled: bool,
+
+    pub webhook_url: Option<String>,
+
+    #[serde(default = "_default_location")]
+    pub location: String,
+
+    #[serde(default = "_default_log_level")]
+    pub log_level: String,
+
+    #[serde(default = "_default_discovery_retries")]
+    pub discovery_retries: u32,
+
+    #[serde(default)]
+    pub fallback_party_sizes: Vec<u8>,
+
+    pub venue_timezone: Option<String>,
+
+    #[serde(default)]
+    pub use_keyring: bool,
+
+    #[serde(default = "_default_request_jitter_ms")]
+    pub request_jitter_ms: u64,
+
+    pub source_id: Option<String>,
+
+    #[serde(default = "_default_max_allowed_party_size")]
+    pub max_allowed_party_size: u8,
+
+    pub api_base_url: Option<String>,
+
+    pub proxy_url: Option<String>,
+
+    #[serde(default = "_default_request_timeout_ms")]
+    pub request_timeout_ms: u64,
+
+    #[serde(default = "_default_connect_timeout_ms")]
+    pub connect_timeout_ms: u64,
+
+    #[serde(default = "_default_max_snipe_horizon_days")]
+    pub max_snipe_horizon_days: u32,
+
+    #[serde(default = "_default_server_side_time_filter")]
+    pub server_side_time_filter: bool,
+
+    pub user_agent: Option<String>,
+
+    #[serde(default)]
+    pub accounts: Vec<Credentials>,
+
+    #[serde(default)]
+    pub prefer_abundant_slots: bool,
+
+    #[serde(default)]
+    pub fire_offset_ms: i64,
+
+    #[serde(default)]
+    pub book_body_format: BookBodyFormat,
+
+    #[serde(default)]
+    pub venue_targets: Vec<VenueTarget>,
+
+    #[serde(default = "_default_snipe_timeout_secs")]
+    pub snipe_timeout_secs: u64,
+
+    #[serde(default)]
+    pub acceptable_dates: Vec<String>,
+
+    pub payment_preference: Option<String>,
+
+    #[serde(default = "_default_max_concurrent_attempts")]
+    pub max_concurrent_attempts: usize,
+}
+
+/// Most recent successful booking, kept so it can be looked up (or cancelled) later.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct BookingRecord {
+    pub resy_token: String,
+    pub venue_slug: String,
+    pub date: String,
+    pub party_size: u8,
+    /// RFC 3339 UTC timestamp of when the booking was made
+    pub booked_at: String,
+}
+
+/// Additional Resy login that `snipe --accounts all` books from alongside the main one.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Credentials {
+    /// label used in logs; defaults to the account's position in the list
+    #[serde(default)]
+    pub name: String,
+
+    pub api_key: String,
+
+    pub auth_token: String,
+
+    /// fetched from the account before the snipe when left empty
+    #[serde(default)]
+    pub payment_id: String,
 }
 
-fn _default_date() -> String {
-    let one_week_later = Utc::now().date_naive() + Duration::days(7);
+/// One venue of a multi-venue snipe; the list order is the booking priority.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct VenueTarget {
+    pub venue_slug: String,
+
+    /// looked up from the slug before the snipe when left empty
+    #[serde(default)]
+    pub venue_id: String,
+}
+
+/// Saved venue/date/party-size preset that can be swapped into the working config.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct VenueProfile {
+    #[serde(default)]
+    pub venue_id: String,
+
+    #[serde(default)]
+    pub venue_slug: String,
+
+    pub venue_name: Option<String>,
+
+    pub venue_neighborhood: Option<String>,
+
+    #[serde(default = "_default_date")]
+    pub date: String,
+
+    #[serde(default = "_default_party_size")]
+    pub party_size: u8,
+
+    pub target_time: Option<String>,
+}
+
+/// How to rank slots whose size bounds sit exactly on the party size.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum FillPreference {
+    /// prefer tables the party fills (min_size == party_size)
+    Tight,
+    /// prefer tables with room to spare (min_size < party_size)
+    Loose,
+}
+
+impl FromStr for FillPreference {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "tight" => Ok(FillPreference::Tight),
+            "loose" => Ok(FillPreference::Loose),
+            _ => Err(format!("invalid fill preference '{}', expected tight or loose", s)),
+        }
+    }
+}
+
+/// The reservation date used when none is configured: one week from today.
+pub fn default_date(clock: &dyn Clock) -> String {
+    let one_week_later = clock.now_utc().date_naive() + Duration::days(7);
     one_week_later.format("%Y-%m-%d").to_string()
 }
 
-fn _default_snipe_date() -> String {
-    (Local::now() + Duration::days(1)).format("%Y-%m-%d").to_string()
+/// The snipe date used when none is configured: tomorrow.
+pub fn default_snipe_date(clock: &dyn Clock) -> String {
+    (clock.now_local() + Duration::days(1)).format("%Y-%m-%d").to_string()
 }
 
+fn _default_date() -> String { default_date(&SystemClock) }
+
+fn _default_snipe_date() -> String { default_snipe_date(&SystemClock) }
+
 const fn _default_party_size() -> u8 { 2 }
 
 fn _default_snipe_time() -> String { String::from("0000") }
 
+const fn _default_max_retries() -> u32 { 5 }
+
+fn _default_ntp_server() -> String { String::from("pool.ntp.org") }
+
+fn _default_location() -> String { String::from("new-york-ny") }
+
+fn _default_log_level() -> String { String::from("info") }
+
+const fn _default_discovery_retries() -> u32 { 10 }
+
+const fn _default_request_jitter_ms() -> u64 { 50 }
+
+const fn _default_max_allowed_party_size() -> u8 { 8 }
+
+const fn _default_request_timeout_ms() -> u64 { 5000 }
+
+const fn _default_connect_timeout_ms() -> u64 { 2000 }
+
+const fn _default_max_snipe_horizon_days() -> u32 { 30 }
+
+const fn _default_server_side_time_filter() -> bool { true }
+
+const fn _default_snipe_timeout_secs() -> u64 { 30 }
+
+const fn _default_max_concurrent_attempts() -> usize { 5 }
+
 impl Default for Config {
     fn default() -> Self {
-        let one_week_later = Utc::now().date_naive() + Duration::days(7);
-        let tmrw = (Local::now() + Duration::days(1)).format("%Y-%m-%d").to_string();
-
         Config {
             api_key: String::new(),
             auth_token: String::new(),
             venue_id: String::new(),
             venue_slug: String::new(),
-            date: one_week_later.format("%Y-%m-%d").to_string(),
+            venue_name: None,
+            venue_neighborhood: None,
+            date: _default_date(),
             party_size: 2,
             target_time: None,
             payment_id: String::new(),
             snipe_time: String::from("0000"),
-            snipe_date: tmrw
+            snipe_date: _default_snipe_date(),
+            fill_preference: None,
+            profiles: HashMap::new(),
+            active_profile: None,
+            max_retries: 5,
+            seating_preference: None,
+            last_booking: None,
+            earliest_time: None,
+            latest_time: None,
+            ntp_server: _default_ntp_server(),
+            notifications_enabled: false,
+            webhook_url: None,
+            location: _default_location(),
+            log_level: _default_log_level(),
+            discovery_retries: 10,
+            fallback_party_sizes: Vec::new(),
+            venue_timezone: None,
+            use_keyring: false,
+            request_jitter_ms: 50,
+            source_id: None,
+            max_allowed_party_size: 8,
+            api_base_url: None,
+            proxy_url: None,
+            request_timeout_ms: 5000,
+            connect_timeout_ms: 2000,
+            max_snipe_horizon_days: 30,
+            server_side_time_filter: true,
+            user_agent: None,
+            accounts: Vec::new(),
+            prefer_abundant_slots: false,
+            fire_offset_ms: 0,
+            book_body_format: BookBodyFormat::default(),
+            venue_targets: Vec::new(),
+            snipe_timeout_secs: 30,
+            acceptable_dates: Vec::new(),
+            payment_preference: None,
+            max_concurrent_attempts: 5,
         }
     }
 }
@@ -79,23 +342,271 @@ impl Clone for Config {
             auth_token: self.auth_token.clone(),
             venue_id: self.venue_id.clone(),
             venue_slug: self.venue_slug.clone(),
+            venue_name: self.venue_name.clone(),
+            venue_neighborhood: self.venue_neighborhood.clone(),
             date: self.date.clone(),
             party_size: self.party_size,
             target_time: self.target_time.clone(),
             payment_id: self.payment_id.clone(),
             snipe_time: self.snipe_time.clone(),
             snipe_date: self.snipe_date.clone(),
+            fill_preference: self.fill_preference,
+            profiles: self.profiles.clone(),
+            active_profile: self.active_profile.clone(),
+            max_retries: self.max_retries,
+            seating_preference: self.seating_preference.clone(),
+            last_booking: self.last_booking.clone(),
+            earliest_time: self.earliest_time.clone(),
+            latest_time: self.latest_time.clone(),
+            ntp_server: self.ntp_server.clone(),
+            notifications_enabled: self.notifications_enabled,
+            webhook_url: self.webhook_url.clone(),
+            location: self.location.clone(),
+            log_level: self.log_level.clone(),
+            discovery_retries: self.discovery_retries,
+            fallback_party_sizes: self.fallback_party_sizes.clone(),
+            venue_timezone: self.venue_timezone.clone(),
+            use_keyring: self.use_keyring,
+            request_jitter_ms: self.request_jitter_ms,
+            source_id: self.source_id.clone(),
+            max_allowed_party_size: self.max_allowed_party_size,
+            api_base_url: self.api_base_url.clone(),
+            proxy_url: self.proxy_url.clone(),
+            request_timeout_ms: self.request_timeout_ms,
+            connect_timeout_ms: self.connect_timeout_ms,
+            max_snipe_horizon_days: self.max_snipe_horizon_days,
+            server_side_time_filter: self.server_side_time_filter,
+            user_agent: self.user_agent.clone(),
+            accounts: self.accounts.clone(),
+            prefer_abundant_slots: self.prefer_abundant_slots,
+            fire_offset_ms: self.fire_offset_ms,
+            book_body_format: self.book_body_format,
+            venue_targets: self.venue_targets.clone(),
+            snipe_timeout_secs: self.snipe_timeout_secs,
+            acceptable_dates: self.acceptable_dates.clone(),
+            payment_preference: self.payment_preference.clone(),
+            max_concurrent_attempts: self.max_concurrent_attempts,
         }
     }
 }
 
 impl Config {
-    pub(crate) fn validate(&self) -> bool {
-        !self.api_key.is_empty() &&
-        !self.auth_token.is_empty() &&
-        !self.venue_id.is_empty() &&
-        !self.date.is_empty() &&
-        self.party_size > 0
+    pub fn validate(&self) -> bool {
+        self.validation_errors().is_empty()
+    }
+
+    /// Like [`Config::validate`], but also requires the payment method a booking is charged to.
+    pub fn validate_for_booking(&self) -> bool {
+        self.booking_validation_errors().is_empty()
+    }
+
+    /// Describes every missing or malformed field needed to look up slots. Booking also needs
+    /// a payment method, see [`Config::booking_validation_errors`].
+    pub fn validation_errors(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+
+        let required = [
+            ("api_key", &self.api_key),
+            ("auth_token", &self.auth_token),
+            ("venue_id", &self.venue_id),
+        ];
+        for (field, value) in required {
+            if value.is_empty() {
+                errors.push(format!("{} is not set", field));
+            }
+        }
+
+        if self.date.is_empty() {
+            errors.push("date is not set".to_string());
+        } else if NaiveDate::parse_from_str(&self.date, "%Y-%m-%d").is_err() {
+            errors.push(format!("date '{}' is not in YYYY-MM-DD format", self.date));
+        }
+
+        for date in &self.acceptable_dates {
+            if NaiveDate::parse_from_str(date, "%Y-%m-%d").is_err() {
+                errors.push(format!("acceptable date '{}' is not in YYYY-MM-DD format", date));
+            }
+        }
+
+        for (index, account) in self.accounts.iter().enumerate() {
+            if account.api_key.is_empty() || account.auth_token.is_empty() {
+                errors.push(format!("accounts[{}] needs both api_key and auth_token", index));
+            }
+        }
+
+        if self.party_size == 0 {
+            errors.push("party_size must be greater than 0".to_string());
+        }
+
+        let times = [
+            ("target_time", &self.target_time),
+            ("earliest_time", &self.earliest_time),
+            ("latest_time", &self.latest_time),
+        ];
+        for (field, time) in times {
+            if let Some(time) = time {
+                if parse_hhmm(time).is_err() {
+                    errors.push(format!("{} '{}' is not in HHMM format", field, time));
+                }
+            }
+        }
+
+        if let Some(timezone) = &self.venue_timezone {
+            if timezone.parse::<chrono_tz::Tz>().is_err() {
+                errors.push(format!("venue_timezone '{}' is not a known IANA timezone", timezone));
+            }
+        }
+
+        errors
+    }
+
+    /// [`Config::validation_errors`] plus the `payment_id` that only booking needs.
+    pub fn booking_validation_errors(&self) -> Vec<String> {
+        let mut errors = self.validation_errors();
+        if self.payment_id.is_empty() {
+            errors.push("payment_id is not set".to_string());
+        }
+        errors
+    }
+
+    /// "Name (Neighborhood)" for the loaded venue, when its name is known.
+    pub fn venue_display_name(&self) -> Option<String> {
+        let name = self.venue_name.as_ref()?;
+        Some(match &self.venue_neighborhood {
+            Some(neighborhood) => format!("{} ({})", name, neighborhood),
+            None => name.clone(),
+        })
+    }
+
+    /// Validates and stores a single setting by its `marksman set` key. Optional settings are cleared with `none`.
+    pub fn set(&mut self, key: &str, value: &str) -> Result<()> {
+        let value = value.trim();
+        let optional = || if value.is_empty() || value.eq_ignore_ascii_case("none") { None } else { Some(value.to_string()) };
+        let hhmm = || -> Result<Option<String>> {
+            match optional() {
+                Some(time) => {
+                    parse_hhmm(&time).map_err(|e| anyhow!("{}", e))?;
+                    Ok(Some(time))
+                }
+                None => Ok(None),
+            }
+        };
+        let date = || -> Result<String> {
+            NaiveDate::parse_from_str(value, "%Y-%m-%d")
+                .map(|date| date.to_string())
+                .map_err(|_| anyhow!("'{}' is not a YYYY-MM-DD date", value))
+        };
+
+        match key {
+            "date" => self.date = date()?,
+            "party-size" => {
+                self.party_size = value.parse::<u8>().ok().filter(|size| *size > 0)
+                    .ok_or_else(|| anyhow!("party-size must be a number from 1 to 255"))?;
+            }
+            "target-time" => self.target_time = hhmm()?,
+            "earliest-time" => self.earliest_time = hhmm()?,
+            "latest-time" => self.latest_time = hhmm()?,
+            "snipe-time" => self.snipe_time = hhmm()?.ok_or_else(|| anyhow!("snipe-time cannot be cleared"))?,
+            "snipe-date" => self.snipe_date = date()?,
+            "seating" => self.seating_preference = optional(),
+            "fill-preference" => {
+                self.fill_preference = optional().map(|preference| preference.parse::<FillPreference>()).transpose().map_err(|e| anyhow!(e))?;
+            }
+            "location" => {
+                self.location = optional().ok_or_else(|| anyhow!("location cannot be cleared"))?;
+            }
+            "venue-timezone" => {
+                let timezone = optional();
+                if let Some(timezone) = &timezone {
+                    timezone.parse::<chrono_tz::Tz>().map_err(|_| anyhow!("'{}' is not a known IANA timezone", timezone))?;
+                }
+                self.venue_timezone = timezone;
+            }
+            "webhook-url" => self.webhook_url = optional(),
+            "payment-preference" => self.payment_preference = optional(),
+            "max-retries" => self.max_retries = value.parse().map_err(|_| anyhow!("max-retries must be a whole number"))?,
+            "log-level" => {
+                self.log_level = value.parse::<log::LevelFilter>()
+                    .map_err(|_| anyhow!("log-level must be one of off, error, warn, info, debug, trace"))?
+                    .to_string().to_lowercase();
+            }
+            "notifications" => {
+                self.notifications_enabled = match value.to_lowercase().as_str() {
+                    "on" | "true" | "yes" => true,
+                    "off" | "false" | "no" => false,
+                    _ => return Err(anyhow!("notifications must be on or off")),
+                };
+            }
+            _ => return Err(anyhow!("Unknown key '{}'. Valid keys: {}", key, SETTABLE_KEYS.join(", "))),
+        }
+        Ok(())
+    }
+
+    /// A default config that keeps only the Resy logins (and where their secrets are stored).
+    pub fn credentials_only(&self) -> Config {
+        Config {
+            api_key: self.api_key.clone(),
+            auth_token: self.auth_token.clone(),
+            payment_id: self.payment_id.clone(),
+            payment_preference: self.payment_preference.clone(),
+            use_keyring: self.use_keyring,
+            accounts: self.accounts.clone(),
+            ..Config::default()
+        }
+    }
+
+    /// Saves the working venue fields as a named profile (overwrites an existing one).
+    pub fn save_profile(&mut self, name: &str) {
+        let profile = VenueProfile {
+            venue_id: self.venue_id.clone(),
+            venue_slug: self.venue_slug.clone(),
+            venue_name: self.venue_name.clone(),
+            venue_neighborhood: self.venue_neighborhood.clone(),
+            date: self.date.clone(),
+            party_size: self.party_size,
+            target_time: self.target_time.clone(),
+        };
+        self.profiles.insert(name.to_string(), profile);
+    }
+
+    /// Loads a named profile into the working fields and marks it active.
+    pub fn use_profile(&mut self, name: &str) -> Result<()> {
+        let profile = self.profiles.get(name)
+            .ok_or_else(|| anyhow!("No profile named '{}'", name))?
+            .clone();
+
+        self.venue_id = profile.venue_id;
+        self.venue_slug = profile.venue_slug;
+        self.venue_name = profile.venue_name;
+        self.venue_neighborhood = profile.venue_neighborhood;
+        self.date = profile.date;
+        self.party_size = profile.party_size;
+        self.target_time = profile.target_time;
+        self.active_profile = Some(name.to_string());
+        Ok(())
+    }
+
+    pub fn remove_profile(&mut self, name: &str) -> bool {
+        if self.active_profile.as_deref() == Some(name) {
+            self.active_profile = None;
+        }
+        self.profiles.remove(name).is_some()
+    }
+
+    /// Resolves the active profile (if any) into the working fields.
+    pub fn resolve_active_profile(&mut self) {
+        if let Some(name) = self.active_profile.clone() {
+            if self.use_profile(&name).is_err() {
+                self.active_profile = None; // profile was removed by hand
+            }
+        }
+    }
+
+    /// Writes working field changes back into the active profile so they persist across runs.
+    pub fn sync_active_profile(&mut self) {
+        if let Some(name) = self.active_profile.clone() {
+            self.save_profile(&name);
+        }
     }
 }
 
@@ -118,20 +629,51 @@ fn init_config(path: &Path) -> Result<()> {
 }
 
 pub fn get_config_path() -> Result<PathBuf> {
-    let path = dirs::home_dir()
-        .map(|path| path.join(".marksman.config"))
-        .context("Could not find home directory")?;
+    let path = default_config_path().context("Could not find home directory")?;
+
+    ensure_config(&path)?;
+    Ok(path)
+}
 
+fn default_config_path() -> Option<PathBuf> {
+    dirs::home_dir().map(|home| home.join(".marksman.config"))
+}
+
+/// Where the history and stats for the config at `config_path` are kept: a `.marksman`
+/// directory next to it, so the default `~/.marksman.config` keeps them in `~/.marksman`.
+pub fn get_data_dir(config_path: &Path) -> PathBuf {
+    config_path.parent().unwrap_or(Path::new("")).join(".marksman")
+}
+
+/// [`get_data_dir`] for the default config, or `None` without a home directory.
+pub fn default_data_dir() -> Option<PathBuf> {
+    default_config_path().map(|path| get_data_dir(&path))
+}
+
+/// Creates a default config at `path` unless one already exists.
+pub fn ensure_config(path: &Path) -> Result<()> {
     if !path.exists() {
-        reset(&path)?;
+        reset(path)?;
     }
-
-    Ok(path)
+    Ok(())
 }
 
 pub fn read_config(path: &Path) -> Result<Config> {
+    // a leftover temp file means a previous write was interrupted; the real config is still intact
+    let temp_path = temp_config_path(path);
+    if temp_path.exists() {
+        fs::remove_file(&temp_path).context("Failed to remove stale temp config file")?;
+    }
+
     let content = fs::read_to_string(path).context("Failed to read config file")?;
-    let config: Config = toml::from_str(&content).context("Failed to deserialize config")?;
+    let mut config: Config = toml::from_str(&content).context("Failed to deserialize config")?;
+
+    if config.use_keyring {
+        if let Err(e) = load_secrets(&mut config, path) {
+            eprintln!("Warning: could not read credentials from the OS keyring ({}), using the config file", e);
+        }
+    }
+
     Ok(config)
 }
 
@@ -142,8 +684,105 @@ pub fn write_config(config: &Config, path: Option<&Path>) -> Result<()> {
             .expect("Unable to determine home directory")
     });
 
-    let config_content = toml::to_string(config).context("Failed to serialize config")?;
-    fs::write(config_path, config_content.as_bytes())
-        .context("Failed to write to config file")?;
+    let config_content = if config.use_keyring {
+        match store_secrets(config, &config_path) {
+            Ok(()) => {
+                // secrets live in the keyring, so keep them out of the file
+                let mut redacted = config.clone();
+                redacted.api_key.clear();
+                redacted.auth_token.clear();
+                for account in &mut redacted.accounts {
+                    account.api_key.clear();
+                    account.auth_token.clear();
+                }
+                toml::to_string(&redacted)
+            }
+            Err(e) => {
+                eprintln!("Warning: could not store credentials in the OS keyring ({}), writing them to the config file", e);
+                toml::to_string(config)
+            }
+        }
+    } else {
+        toml::to_string(config)
+    }.context("Failed to serialize config")?;
+
+    // write a sibling temp file and rename it over the config so an interrupted write can't truncate it
+    let temp_path = temp_config_path(&config_path);
+    fs::write(&temp_path, config_content.as_bytes())
+        .context("Failed to write to temp config file")?;
+    fs::rename(&temp_path, &config_path)
+        .context("Failed to replace config file")?;
+    Ok(())
+}
+
+fn temp_config_path(path: &Path) -> PathBuf {
+    let mut file_name = path.file_name().unwrap_or_default().to_os_string();
+    file_name.push(".tmp");
+    path.with_file_name(file_name)
+}
+
+const KEYRING_SERVICE: &str = "marksman";
+
+/// Keyring user name for `field` of the config at `config_path`, so every config file keeps its
+/// own credentials. Account fields are named `accounts.<name or position>.<field>`.
+pub fn keyring_user(config_path: &Path, field: &str) -> String {
+    // the config may not exist yet on its first write, so resolve the directory instead
+    let parent = config_path.parent().unwrap_or(Path::new(""));
+    let dir = fs::canonicalize(if parent.as_os_str().is_empty() { Path::new(".") } else { parent })
+        .or_else(|_| std::path::absolute(parent))
+        .unwrap_or_else(|_| parent.to_path_buf());
+    let absolute = match config_path.file_name() {
+        Some(file_name) => dir.join(file_name),
+        None => config_path.to_path_buf(),
+    };
+    format!("{}:{}", absolute.display(), field)
+}
+
+fn account_field(index: usize, account: &Credentials, field: &str) -> String {
+    if account.name.is_empty() {
+        format!("accounts.{}.{}", index, field)
+    } else {
+        format!("accounts.{}.{}", account.name, field)
+    }
+}
+
+/// Fills the secret fields from the keyring, keeping the file values for any entry not stored yet.
+fn load_secrets(config: &mut Config, config_path: &Path) -> keyring::Result<()> {
+    if let Some(api_key) = read_secret(config_path, "api_key")? {
+        config.api_key = api_key;
+    }
+    if let Some(auth_token) = read_secret(config_path, "auth_token")? {
+        config.auth_token = auth_token;
+    }
+    for (index, account) in config.accounts.iter_mut().enumerate() {
+        if let Some(api_key) = read_secret(config_path, &account_field(index, account, "api_key"))? {
+            account.api_key = api_key;
+        }
+        if let Some(auth_token) = read_secret(config_path, &account_field(index, account, "auth_token"))? {
+            account.auth_token = auth_token;
+        }
+    }
+    Ok(())
+}
+
+fn read_secret(config_path: &Path, field: &str) -> keyring::Result<Option<String>> {
+    match keyring::Entry::new(KEYRING_SERVICE, &keyring_user(config_path, field))?.get_password() {
+        Ok(secret) => Ok(Some(secret)),
+        Err(keyring::Error::NoEntry) => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+fn store_secret(config_path: &Path, field: &str, secret: &str) -> keyring::Result<()> {
+    keyring::Entry::new(KEYRING_SERVICE, &keyring_user(config_path, field))?.set_password(secret)
+}
+
+fn store_secrets(config: &Config, config_path: &Path) -> keyring::Result<()> {
+    store_secret(config_path, "api_key", &config.api_key)?;
+    store_secret(config_path, "auth_token", &config.auth_token)?;
+    for (index, account) in config.accounts.iter().enumerate() {
+        store_secret(config_path, &account_field(index, account, "api_key"), &account.api_key)?;
+        store_secret(config_path, &account_field(index, account, "auth_token"), &account.auth_token)?;
+    }
     Ok(())
 }
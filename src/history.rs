@@ -0,0 +1,53 @@
+// history.rs
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+/// One snipe result, stored as a line of `history.jsonl` in the data directory (`~/.marksman` by default).
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct HistoryEntry {
+    /// RFC3339 time the snipe finished
+    pub recorded_at: String,
+    pub venue_slug: String,
+    pub date: String,
+    /// booked slot start, unset for failures
+    pub time: Option<String>,
+    pub party_size: u8,
+    pub success: bool,
+    pub resy_token: Option<String>,
+    /// why the snipe failed, unset for successes
+    pub reason: Option<String>,
+}
+
+pub fn get_history_path(data_dir: &Path) -> PathBuf {
+    data_dir.join("history.jsonl")
+}
+
+pub fn append(data_dir: &Path, entry: &HistoryEntry) -> Result<()> {
+    let path = get_history_path(data_dir);
+    fs::create_dir_all(data_dir).context("Failed to create history directory")?;
+
+    let line = serde_json::to_string(entry).context("Failed to serialize history entry")?;
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .context("Failed to open history file")?;
+    writeln!(file, "{}", line).context("Failed to write history entry")?;
+    Ok(())
+}
+
+/// Reads every entry, oldest first; lines that fail to parse are skipped.
+pub fn read_all(data_dir: &Path) -> Result<Vec<HistoryEntry>> {
+    let path = get_history_path(data_dir);
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let content = fs::read_to_string(&path).context("Failed to read history file")?;
+    Ok(content.lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect())
+}
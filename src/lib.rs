@@ -0,0 +1,20 @@
+//! Resy reservation sniping, usable from the `marksman` CLI or embedded in other tools.
+//!
+//! Start from [`ResyClient`]; [`ResyAPIGateway`] is exposed for direct access to the raw API.
+
+#[macro_use] extern crate prettytable;
+
+pub mod resy_client;
+pub mod config;
+pub mod resy_api_gateway;
+pub mod view_utils;
+pub mod logging;
+pub mod history;
+pub mod stats;
+pub mod clock;
+mod ntp;
+mod notifier;
+
+pub use config::Config;
+pub use resy_api_gateway::ResyAPIGateway;
+pub use resy_client::{BookingOutcome, LoginOutcome, ResyClient, ResyClientError, ResySlot, SnipeEvent, SnipeMetrics, SnipeOptions, VenueInfo, WatchOptions, WatchOutcome};
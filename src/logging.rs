@@ -0,0 +1,57 @@
+// logging.rs
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use anyhow::{Context, Result};
+use env_logger::{Builder, Env, Target};
+
+// log files past this size are rotated to `<path>.1` on startup
+const MAX_LOG_FILE_BYTES: u64 = 10 * 1024 * 1024;
+
+/// Writes every log line to stderr and to the log file.
+struct TeeWriter {
+    file: File,
+}
+
+impl Write for TeeWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        io::stderr().write_all(buf)?;
+        self.file.write_all(buf)?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        io::stderr().flush()?;
+        self.file.flush()
+    }
+}
+
+/// Sets up env_logger (RUST_LOG overrides `log_level`), optionally teeing output to `log_file`.
+pub fn init(log_level: &str, log_file: Option<&Path>) -> Result<()> {
+    let mut builder = Builder::from_env(Env::default().default_filter_or(log_level));
+    builder.format_timestamp_millis();
+
+    if let Some(path) = log_file {
+        let file = open_log_file(path)?;
+        builder.target(Target::Pipe(Box::new(TeeWriter { file })));
+    }
+
+    builder.init();
+    Ok(())
+}
+
+fn open_log_file(path: &Path) -> Result<File> {
+    if let Ok(metadata) = fs::metadata(path) {
+        if metadata.len() > MAX_LOG_FILE_BYTES {
+            let mut rotated = PathBuf::from(path);
+            rotated.as_mut_os_string().push(".1");
+            fs::rename(path, &rotated).context("Failed to rotate log file")?;
+        }
+    }
+
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .context("Failed to open log file")
+}
@@ -1,37 +1,144 @@
-#[macro_use] extern crate prettytable;
+use std::env;
 use std::io;
 use clap::{Command, Arg, ArgAction};
-use std::io::Write;
+use clap_complete::Shell;
+use std::io::{IsTerminal, Write};
 use anyhow::{Context, Result};
 use regex::Regex;
-use resy_client::ResyClient;
+use marksman::clock::SimulatedClock;
+use marksman::resy_client::{parse_hhmm, LoginOutcome, resolve_local_datetime, sort_slots, ResyClient, ResyClientError, ResySlot, SlotSort, SnipeOptions, WatchOptions, WatchOutcome};
+use marksman::config::{self, FillPreference};
+use marksman::{history, logging, stats, view_utils};
 use std::sync::Arc;
-use env_logger::{Env};
-use chrono::{Local, Duration};
+use std::path::{Path, PathBuf};
+use chrono::{DateTime, Local, NaiveDate, NaiveDateTime, Utc};
 
-mod resy_client;
-mod config;
-mod resy_api_gateway;
-mod view_utils;
+const AUTH_EXPIRED_MESSAGE: &str = "Your auth token has expired, run `marksman load` to refresh";
+// read by `login` for scripted use; otherwise the password is prompted for without echo
+const PASSWORD_ENV_VAR: &str = "MARKSMAN_PASSWORD";
+// the shell's status for a Ctrl-C, used whenever the user stops a command
+const CANCELLED_EXIT_CODE: i32 = 130;
 
-#[tokio::main]
-async fn main() -> Result<()> {
+/// Normalizes "1930", "19:30", "7:30pm" and "7pm" style times to the HHMM format used internally.
+fn parse_friendly_time(input: &str) -> Result<String, String> {
+    let invalid = || format!("Invalid time '{}'. Accepted formats: 1930, 19:30, 7:30pm, 7pm", input);
 
-    // setup logging
-    let env = Env::default().default_filter_or("none");
-    env_logger::init_from_env(env);
+    let time_regex = Regex::new(r"^(\d{1,2})(?::?(\d{2}))?\s*(am|pm)?$").unwrap();
+    let normalized = input.trim().to_lowercase();
+    let captures = time_regex.captures(&normalized).ok_or_else(invalid)?;
 
-    let config_path = config::get_config_path().context("Failed to get config path")?;
-    let marks_config = config::read_config(&config_path)
-        .expect("Failed to load configuration");
+    let mut hours: u32 = captures[1].parse().map_err(|_| invalid())?;
+    let minutes: u32 = captures.get(2).map_or(Ok(0), |m| m.as_str().parse()).map_err(|_| invalid())?;
+
+    if let Some(meridiem) = captures.get(3) {
+        if !(1..=12).contains(&hours) {
+            return Err(invalid());
+        }
+        hours = match (meridiem.as_str(), hours) {
+            ("am", 12) => 0,
+            ("pm", 12) => 12,
+            ("pm", h) => h + 12,
+            (_, h) => h,
+        };
+    }
+
+    let hhmm = format!("{:02}{:02}", hours, minutes);
+    parse_hhmm(&hhmm).map_err(|_| invalid())?;
+    Ok(hhmm)
+}
+
+/// Prints why a command failed; with `--quiet` it goes to stderr so stdout only ever holds results.
+fn report_failure(quiet: bool, message: impl std::fmt::Display) {
+    if quiet {
+        eprintln!("{}", message);
+    } else {
+        println!("{}", message);
+    }
+}
 
-    let mut resy_client = ResyClient::from_config(marks_config);
+/// Parses `--simulate-time`: RFC 3339, or a local "YYYY-MM-DDTHH:MM[:SS]".
+fn parse_simulate_time(input: &str) -> Result<DateTime<Utc>, String> {
+    if let Ok(datetime) = DateTime::parse_from_rfc3339(input) {
+        return Ok(datetime.with_timezone(&Utc));
+    }
+    ["%Y-%m-%dT%H:%M:%S", "%Y-%m-%dT%H:%M", "%Y-%m-%d %H:%M:%S", "%Y-%m-%d %H:%M"].iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(input, format).ok())
+        .and_then(|naive| resolve_local_datetime(&Local, naive))
+        .map(|datetime| datetime.with_timezone(&Utc))
+        .ok_or_else(|| format!("Invalid time '{}'. Use e.g. 2024-05-20T09:59:30 or 2024-05-20T09:59:30-04:00", input))
+}
 
-    // define cli commands
-    let cli = Command::new("marksman")
+/// Writes slots to `path` as pretty JSON, or as CSV via `view_utils::write_csv`.
+fn write_slots_file(path: &Path, slots: &[ResySlot], csv: bool) -> Result<()> {
+    if let Some(parent) = path.parent().filter(|parent| !parent.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("creating {}", parent.display()))?;
+    }
+    let mut file = std::fs::File::create(path)?;
+    if csv {
+        view_utils::write_csv(slots, &mut file)?;
+    } else {
+        serde_json::to_writer_pretty(&mut file, slots)?;
+        writeln!(file)?;
+    }
+    Ok(())
+}
+
+/// The full command definition, shared by argument parsing and completion generation.
+fn build_cli() -> Command {
+    Command::new("marksman")
         .version("0.1.0")
         .author("Anish Agrawal")
         .about("Snipe reservations in NYC")
+        .arg(
+            Arg::new("log-level")
+                .help("Log level (error, warn, info, debug, trace, off). RUST_LOG takes precedence")
+                .value_parser(["error", "warn", "info", "debug", "trace", "off"])
+                .long("log-level")
+                .global(true)
+                .required(false),
+        )
+        .arg(
+            Arg::new("log-file")
+                .help("Also append timestamped logs to this file")
+                .value_parser(clap::value_parser!(PathBuf))
+                .long("log-file")
+                .global(true)
+                .required(false),
+        )
+        .arg(
+            Arg::new("proxy")
+                .help("HTTP or SOCKS proxy for every Resy request, e.g. socks5://127.0.0.1:1080 (saved to config)")
+                .value_parser(clap::builder::NonEmptyStringValueParser::new())
+                .long("proxy")
+                .global(true)
+                .required(false),
+        )
+        .arg(
+            Arg::new("config")
+                .help("Config file to use instead of ~/.marksman.config (created if missing)")
+                .value_parser(clap::value_parser!(PathBuf))
+                .long("config")
+                .global(true)
+                .required(false),
+        )
+        .arg(
+            Arg::new("quiet")
+                .help("Only print the result (e.g. the booking token) to stdout and errors to stderr; logs are limited to errors")
+                .long("quiet")
+                .short('q')
+                .global(true)
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("simulate-time")
+                .help("Pretend the clock reads this time when the command starts, to exercise the countdown without waiting")
+                .value_parser(parse_simulate_time)
+                .long("simulate-time")
+                .global(true)
+                .hide(true)
+                .required(false),
+        )
         .subcommand(
             Command::new("hello")
                 .about("Prints greeting")
@@ -70,8 +177,8 @@ async fn main() -> Result<()> {
                 )
                 .arg(
                     Arg::new("target-time")
-                        .help("Target time for Resy booking (HHMM)")
-                        .value_parser(clap::builder::NonEmptyStringValueParser::new())
+                        .help("Target time for Resy booking (e.g. 1930, 19:30, 7:30pm, 7pm)")
+                        .value_parser(parse_friendly_time)
                         .short('t')
                         .long("target-time")
                         .required(false),
@@ -82,6 +189,134 @@ async fn main() -> Result<()> {
                         .short('r')
                         .long("reset-time")
                         .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("fill-preference")
+                        .help("Rank slots whose min size equals the party size first (tight) or last (loose)")
+                        .value_parser(|s: &str| s.parse::<FillPreference>())
+                        .long("fill-preference")
+                        .required(false),
+                )
+                .arg(
+                    Arg::new("seating")
+                        .help("Only book slots whose seating type contains this (e.g. patio, bar)")
+                        .value_parser(clap::builder::NonEmptyStringValueParser::new())
+                        .long("seating")
+                        .required(false),
+                )
+                .arg(
+                    Arg::new("earliest")
+                        .help("Earliest acceptable slot start time (e.g. 1900, 19:00, 7pm)")
+                        .value_parser(parse_friendly_time)
+                        .long("earliest")
+                        .required(false),
+                )
+                .arg(
+                    Arg::new("latest")
+                        .help("Latest acceptable slot start time (e.g. 2100, 21:00, 9pm)")
+                        .value_parser(parse_friendly_time)
+                        .long("latest")
+                        .required(false),
+                )
+                .arg(
+                    Arg::new("json")
+                        .help("Print slots as JSON instead of a table")
+                        .long("json")
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("output-file")
+                        .help("Also write the slots to this file (parent directories are created)")
+                        .value_name("PATH")
+                        .value_parser(clap::value_parser!(PathBuf))
+                        .long("output-file")
+                        .conflicts_with("sweep"),
+                )
+                .arg(
+                    Arg::new("format")
+                        .help("Print slots as json or csv instead of a table; also the --output-file format (default json)")
+                        .value_parser(["json", "csv"])
+                        .long("format")
+                        .conflicts_with("sweep"),
+                )
+                .arg(
+                    Arg::new("force-large")
+                        .help("Allow party sizes above the configured max_allowed_party_size")
+                        .long("force-large")
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("refresh-venue")
+                        .help("Look up the venue id again even if this venue is already loaded")
+                        .long("refresh-venue")
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("verbose-slots")
+                        .help("Log raw vs parsed slot counts and any raw slot that could not be parsed")
+                        .long("verbose-slots")
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("raw")
+                        .help("Also show the raw start/end timestamps in the slot table")
+                        .long("raw")
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("sort")
+                        .help("Order the listed slots by time, type or size (default: closest to target time, else time)")
+                        .value_parser(|s: &str| s.parse::<SlotSort>())
+                        .long("sort")
+                        .required(false),
+                )
+                .arg(
+                    Arg::new("plain")
+                        .help("Print slots as tab-separated rows without borders (the default when stdout isn't a terminal)")
+                        .long("plain")
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("no-color")
+                        .help("Don't color slot rows by seating type")
+                        .long("no-color")
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("sweep")
+                        .help("Show availability for every party size from 1 up to MAX (default 6)")
+                        .value_name("MAX")
+                        .value_parser(clap::value_parser!(u8).range(1..))
+                        .long("sweep")
+                        .num_args(0..=1)
+                        .default_missing_value("6")
+                        .conflicts_with("json"),
+                )
+                .arg(
+                    Arg::new("location")
+                        .help("Resy location slug used to look up the venue (e.g. los-angeles-ca)")
+                        .value_parser(clap::builder::NonEmptyStringValueParser::new())
+                        .short('l')
+                        .long("location")
+                        .required(false),
+                ),
+        )
+        .subcommand(
+            Command::new("find")
+                .about("Search venues by name")
+                .arg(
+                    Arg::new("query")
+                        .help("Venue name to search for")
+                        .value_parser(clap::builder::NonEmptyStringValueParser::new())
+                        .required(true),
+                )
+                .arg(
+                    Arg::new("location")
+                        .help("Resy location slug to search in (e.g. los-angeles-ca)")
+                        .value_parser(clap::builder::NonEmptyStringValueParser::new())
+                        .short('l')
+                        .long("location")
+                        .required(false),
                 ),
         )
         .subcommand(
@@ -93,19 +328,66 @@ async fn main() -> Result<()> {
                         .short('s')
                         .long("skip")
                         .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("select")
+                        .help("choose which payment method to book with")
+                        .long("select")
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("keyring")
+                        .help("store credentials in the OS keyring instead of the config file")
+                        .long("keyring")
+                        .action(ArgAction::SetTrue),
+                ),
+        )
+        .subcommand(
+            Command::new("login")
+                .about("Log in with your Resy email and password to load a fresh auth token (password from $MARKSMAN_PASSWORD or prompted for)")
+                .arg(
+                    Arg::new("email")
+                        .help("Resy account email")
+                        .long("email")
+                        .required(true),
+                )
+                .arg(
+                    Arg::new("api-key")
+                        .help("Resy API key to use and save (defaults to the configured api_key)")
+                        .value_parser(clap::builder::NonEmptyStringValueParser::new())
+                        .long("api-key")
+                        .required(false),
                 ),
         )
         .subcommand(
             Command::new("state")
                 .about("current marksman configuration")
         )
+        .subcommand(
+            Command::new("history")
+                .about("past bookings made by marksman")
+                .arg(
+                    Arg::new("failures")
+                        .help("Include failed snipe attempts")
+                        .long("failures")
+                        .action(ArgAction::SetTrue),
+                )
+        )
+        .subcommand(
+            Command::new("stats")
+                .about("snipe success rates per venue and fire offset")
+        )
+        .subcommand(
+            Command::new("validate")
+                .about("check the configuration is complete before sniping")
+        )
         .subcommand(
             Command::new("snipe")
                 .about("configure sniper for the reservation")
                 .arg(
                     Arg::new("snipe-time")
-                        .help("Snipe time for Resy booking (HHMM)")
-                        .value_parser(clap::builder::NonEmptyStringValueParser::new())
+                        .help("Snipe time for Resy booking (e.g. 0000, 00:00, 12am)")
+                        .value_parser(parse_friendly_time)
                         .short('t')
                         .long("snipe-time")
                         .required(false),
@@ -118,14 +400,361 @@ async fn main() -> Result<()> {
                         .long("snipe-date")
                         .required(false),
                 )
+                .arg(
+                    Arg::new("fill-preference")
+                        .help("Rank slots whose min size equals the party size first (tight) or last (loose)")
+                        .value_parser(|s: &str| s.parse::<FillPreference>())
+                        .long("fill-preference")
+                        .required(false),
+                )
+                .arg(
+                    Arg::new("seating")
+                        .help("Only book slots whose seating type contains this (e.g. patio, bar)")
+                        .value_parser(clap::builder::NonEmptyStringValueParser::new())
+                        .long("seating")
+                        .required(false),
+                )
+                .arg(
+                    Arg::new("countdown")
+                        .help("Show an in-place countdown until snipe time")
+                        .long("countdown")
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("slot-rank")
+                        .help("Start booking attempts at the Nth closest slot, wrapping around (default 1)")
+                        .value_parser(clap::value_parser!(u64).range(1..))
+                        .long("slot-rank")
+                        .required(false),
+                )
+                .arg(
+                    Arg::new("token")
+                        .help("Book this slot config token directly, skipping slot discovery")
+                        .value_parser(clap::builder::NonEmptyStringValueParser::new())
+                        .long("token")
+                        .conflicts_with("dry-run")
+                        .required(false),
+                )
+                .arg(
+                    Arg::new("force-large")
+                        .help("Allow party sizes above the configured max_allowed_party_size")
+                        .long("force-large")
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("dry-run")
+                        .help("Run discovery and fetch reservation details without booking")
+                        .long("dry-run")
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("earliest")
+                        .help("Earliest acceptable slot start time (e.g. 1900, 19:00, 7pm)")
+                        .value_parser(parse_friendly_time)
+                        .long("earliest")
+                        .required(false),
+                )
+                .arg(
+                    Arg::new("latest")
+                        .help("Latest acceptable slot start time (e.g. 2100, 21:00, 9pm)")
+                        .value_parser(parse_friendly_time)
+                        .long("latest")
+                        .required(false),
+                )
+                .arg(
+                    Arg::new("fallback-sizes")
+                        .help("Party sizes to try in order when none are available at the configured size (e.g. 2,3)")
+                        .value_parser(clap::value_parser!(u8))
+                        .value_delimiter(',')
+                        .long("fallback-sizes")
+                        .required(false),
+                )
+                .arg(
+                    Arg::new("dates")
+                        .help("Reservation dates that are all acceptable (e.g. 2024-05-17,2024-05-18); the slot closest to the target time on any of them is booked")
+                        .value_parser(|s: &str| NaiveDate::parse_from_str(s, "%Y-%m-%d").map(|date| date.to_string()).map_err(|_| format!("Invalid date '{}', expected YYYY-MM-DD", s)))
+                        .value_delimiter(',')
+                        .long("dates")
+                        .conflicts_with("token")
+                        .required(false),
+                )
+                .arg(
+                    Arg::new("sync-clock")
+                        .help("Measure local clock drift against the configured NTP server and correct for it")
+                        .long("sync-clock")
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("notify")
+                        .help("Show a desktop notification when the snipe finishes")
+                        .long("notify")
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("webhook")
+                        .help("Webhook URL to post the result to (overrides the configured webhook_url)")
+                        .value_parser(clap::builder::NonEmptyStringValueParser::new())
+                        .long("webhook")
+                        .required(false),
+                )
+                .arg(
+                    Arg::new("from-file")
+                        .help("Book from slots saved with `venue --json` instead of discovering them at snipe time")
+                        .value_parser(clap::value_parser!(PathBuf))
+                        .long("from-file")
+                        .conflicts_with_all(["token", "dry-run"]),
+                )
+                .arg(
+                    Arg::new("venues")
+                        .help("Snipe these venue slugs instead of the configured venue, booking in the given priority order (e.g. slug1,slug2)")
+                        .value_parser(clap::builder::NonEmptyStringValueParser::new())
+                        .value_delimiter(',')
+                        .long("venues")
+                        .conflicts_with("token")
+                        .required(false),
+                )
+                .arg(
+                    Arg::new("confirm")
+                        .help("Show the snipe plan and fire time and ask before waiting (skipped when not run from a terminal)")
+                        .long("confirm")
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("book-first")
+                        .help("Greedy: book whichever slot gets through first, unranked and unstaggered (faster, but may be far from the target time)")
+                        .long("book-first")
+                        .action(ArgAction::SetTrue)
+                        .conflicts_with_all(["book-best", "slot-rank"]),
+                )
+                .arg(
+                    Arg::new("book-best")
+                        .help("Try the slots closest to the target time first (default)")
+                        .long("book-best")
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("accounts")
+                        .help("Also snipe from every account listed under `accounts` in the config, keeping the first booking")
+                        .value_parser(["all"])
+                        .long("accounts")
+                        .required(false),
+                )
+                .arg(
+                    Arg::new("force")
+                        .help("Allow a snipe time further out than the configured max_snipe_horizon_days")
+                        .long("force")
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("repeat")
+                        .help("Re-run discovery and booking after a failure, up to this many attempts in total")
+                        .value_parser(clap::value_parser!(u32).range(1..))
+                        .long("repeat")
+                        .default_value("1"),
+                )
+                .arg(
+                    Arg::new("interval")
+                        .help("Minutes to wait between repeated attempts")
+                        .value_parser(clap::value_parser!(u64))
+                        .long("interval")
+                        .default_value("15"),
+                )
+        )
+        .subcommand(
+            Command::new("schedule")
+                .about("snipe the drop for a dining date released a fixed number of days in advance")
+                .arg(
+                    Arg::new("date")
+                        .help("Dining date to book (YYYY-MM-DD)")
+                        .value_parser(clap::builder::NonEmptyStringValueParser::new())
+                        .short('d')
+                        .long("date")
+                        .required(true),
+                )
+                .arg(
+                    Arg::new("days-before")
+                        .help("How many days before the dining date reservations are released")
+                        .value_parser(clap::value_parser!(u32))
+                        .long("days-before")
+                        .default_value("7"),
+                )
+                .arg(
+                    Arg::new("drop-time")
+                        .help("Local time reservations are released (e.g. 0000, 00:00, 12am)")
+                        .value_parser(parse_friendly_time)
+                        .long("drop-time")
+                        .default_value("0000"),
+                )
+                .arg(
+                    Arg::new("countdown")
+                        .help("Show an in-place countdown until snipe time")
+                        .long("countdown")
+                        .action(ArgAction::SetTrue),
+                )
+        )
+        .subcommand(
+            Command::new("watch")
+                .about("poll the configured venue and date for cancellations until a slot opens up")
+                .arg(
+                    Arg::new("interval")
+                        .help("Seconds between availability checks")
+                        .value_parser(clap::value_parser!(u64).range(1..))
+                        .long("interval")
+                        .default_value("30"),
+                )
+                .arg(
+                    Arg::new("max-duration")
+                        .help("Stop watching after this many hours (e.g. 2, 0.5)")
+                        .value_parser(clap::value_parser!(f64))
+                        .long("max-duration")
+                        .required(false),
+                )
+                .arg(
+                    Arg::new("auto-book")
+                        .help("Book the first matching slot instead of just reporting it")
+                        .long("auto-book")
+                        .action(ArgAction::SetTrue),
+                )
+        )
+        .subcommand(
+            Command::new("payment")
+                .about("list or choose the payment method used for bookings")
+                .subcommand_required(true)
+                .subcommand(
+                    Command::new("list")
+                        .about("list the payment methods on the resy account"),
+                )
+                .subcommand(
+                    Command::new("set")
+                        .about("book with this payment method id")
+                        .arg(Arg::new("id").help("Payment method id (see `payment list`)").required(true)),
+                )
+                .subcommand(
+                    Command::new("prefer")
+                        .about("book with the card matching this preference, re-resolved on every `load`")
+                        .arg(Arg::new("preference").help("\"default\", a card type such as amex, or a payment method id").required(true)),
+                )
+        )
+        .subcommand(
+            Command::new("profile")
+                .about("manage saved venue profiles")
+                .subcommand_required(true)
+                .subcommand(
+                    Command::new("add")
+                        .about("save the current venue settings as a profile")
+                        .arg(Arg::new("name").help("Profile name").required(true)),
+                )
+                .subcommand(
+                    Command::new("use")
+                        .about("switch to a saved profile")
+                        .arg(Arg::new("name").help("Profile name").required(true)),
+                )
+                .subcommand(
+                    Command::new("list")
+                        .about("list saved profiles"),
+                )
+                .subcommand(
+                    Command::new("rm")
+                        .about("remove a saved profile")
+                        .arg(Arg::new("name").help("Profile name").required(true)),
+                )
         )
         .subcommand(
             Command::new("setup")
                 .about("configure setup wizard")
-        );
+        )
+        .subcommand(
+            Command::new("set")
+                .about("update a single configuration value")
+                .arg(
+                    Arg::new("key")
+                        .help("Setting to change (e.g. date, party-size, target-time, venue-url)")
+                        .required(true),
+                )
+                .arg(
+                    Arg::new("value")
+                        .help("New value; optional settings are cleared with 'none'")
+                        .required(true),
+                )
+        )
+        .subcommand(
+            Command::new("reset")
+                .about("reset the configuration to defaults, keeping credentials")
+                .arg(
+                    Arg::new("all")
+                        .help("Also clear the API key, auth token, payment id and extra accounts")
+                        .long("all")
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("yes")
+                        .help("Skip the confirmation prompt")
+                        .short('y')
+                        .long("yes")
+                        .action(ArgAction::SetTrue),
+                )
+        )
+        .subcommand(
+            Command::new("completions")
+                .about("print a shell completion script (e.g. marksman completions zsh > _marksman)")
+                .arg(
+                    Arg::new("shell")
+                        .help("Shell to generate completions for")
+                        .value_parser(clap::value_parser!(Shell))
+                        .required(true),
+                )
+        )
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
 
     // parse cli
-    let matches = cli.get_matches();
+    let matches = build_cli().get_matches();
+
+    // completions only need the command definition, so skip loading the config
+    if let Some(("completions", sub_matches)) = matches.subcommand() {
+        let shell = *sub_matches.get_one::<Shell>("shell").unwrap();
+        clap_complete::generate(shell, &mut build_cli(), "marksman", &mut io::stdout());
+        return Ok(());
+    }
+
+    let config_path = match matches.get_one::<PathBuf>("config") {
+        Some(path) => config::ensure_config(path).map(|_| path.clone()).context("Failed to create config file")?,
+        None => config::get_config_path().context("Failed to get config path")?,
+    };
+    let mut marks_config = config::read_config(&config_path)
+        .expect("Failed to load configuration");
+
+    // with --quiet stdout carries only each command's result
+    let quiet = matches.get_flag("quiet");
+
+    // setup logging (RUST_LOG > --quiet > --log-level > config)
+    let log_level = if quiet {
+        "error"
+    } else {
+        matches.get_one::<String>("log-level").unwrap_or(&marks_config.log_level)
+    };
+    let log_file = matches.get_one::<PathBuf>("log-file");
+    logging::init(log_level, log_file.map(PathBuf::as_path))?;
+
+    if let Some(proxy) = matches.get_one::<String>("proxy") {
+        marks_config.proxy_url = Some(proxy.clone());
+    }
+
+    // history and stats follow the config, so each --config keeps its own
+    let data_dir = config::get_data_dir(&config_path);
+    let mut resy_client = ResyClient::from_config(marks_config).context("Failed to set up the Resy client")?;
+    resy_client.set_data_dir(data_dir.clone());
+    if let Some(start) = matches.get_one::<DateTime<Utc>>("simulate-time") {
+        eprintln!("Simulating the clock from {}", start.with_timezone(&Local).format("%Y-%m-%d %H:%M:%S"));
+        resy_client.set_clock(Arc::new(SimulatedClock::starting_at(*start)));
+    }
+
+    // set by any failed command so scripts can detect it, after the config is written
+    let mut failed = false;
+    // set when the user stops a snipe or watch, which is neither a success nor a failure
+    let mut cancelled = false;
 
     // handling subcommands
     match matches.subcommand() {
@@ -149,15 +778,168 @@ async fn main() -> Result<()> {
                 target_time = None;
             }
 
-            match resy_client.view_venue(url, date, party_size, target_time).await {
-                Ok((_, slots)) => {
-                    println!("venue details loaded successfully");
-                    view_utils::print_table(&slots);
+            if let Some(fill_preference) = sub_matches.get_one::<FillPreference>("fill-preference") {
+                resy_client.config.fill_preference = Some(*fill_preference);
+            }
+
+            if let Some(seating) = sub_matches.get_one::<String>("seating") {
+                resy_client.config.seating_preference = Some(seating.clone());
+            }
+
+            if let Some(earliest) = sub_matches.get_one::<String>("earliest") {
+                resy_client.config.earliest_time = Some(earliest.clone());
+            }
+
+            if let Some(latest) = sub_matches.get_one::<String>("latest") {
+                resy_client.config.latest_time = Some(latest.clone());
+            }
+
+            if let Some(location) = sub_matches.get_one::<String>("location") {
+                resy_client.config.location = location.clone();
+            }
+
+            if sub_matches.get_flag("refresh-venue") {
+                resy_client.config.venue_id.clear();
+            }
+
+            resy_client.set_verbose_slots(sub_matches.get_flag("verbose-slots"));
+
+            // in json/csv mode stdout carries only the slot data
+            let format = sub_matches.get_one::<String>("format").map(String::as_str);
+            let json = sub_matches.get_flag("json") || format == Some("json");
+            let quiet = quiet || json || format == Some("csv");
+            let raw = sub_matches.get_flag("raw");
+            let color = !sub_matches.get_flag("no-color") && io::stdout().is_terminal();
+            // box drawing only helps a person reading a terminal
+            let plain = sub_matches.get_flag("plain") || !io::stdout().is_terminal();
+            let sort = sub_matches.get_one::<SlotSort>("sort").copied();
+
+            let sweep = sub_matches.get_one::<u8>("sweep").copied();
+            let output_file = sub_matches.get_one::<PathBuf>("output-file");
+
+            let result = resy_client.view_venue(url, date, party_size, target_time, sub_matches.get_flag("force-large")).await;
+            if let (Some(_), Ok(_), Some(venue)) = (url, &result, resy_client.config.venue_display_name()) {
+                if quiet {
+                    eprintln!("Loaded: {}", venue);
+                } else {
+                    println!("Loaded: {}", venue);
+                }
+            }
+            if let (Ok(_), Some(venue_info)) = (&result, resy_client.venue_info()) {
+                for line in view_utils::venue_info_lines(venue_info) {
+                    if quiet {
+                        eprintln!("{}", line);
+                    } else {
+                        println!("{}", line);
+                    }
+                }
+            }
+
+            match (result, sweep) {
+                (Ok(_), Some(max_party_size)) => {
+                    match resy_client.sweep_party_sizes(max_party_size).await {
+                        Ok(sweeps) => {
+                            for (party_size, mut slots) in sweeps {
+                                if let Some(sort) = sort {
+                                    sort_slots(&mut slots, sort);
+                                }
+                                println!("Party of {}:", party_size);
+                                view_utils::print_table(&slots, raw, color, plain);
+                            }
+                        }
+                        Err(ResyClientError::AuthExpired) => {
+                            report_failure(quiet, AUTH_EXPIRED_MESSAGE);
+                            failed = true;
+                        }
+                        Err(e) => {
+                            report_failure(quiet, format!("Failed to sweep party sizes: {}", e));
+                            failed = true;
+                        }
+                    }
+                }
+                (Ok((_, mut slots)), None) => {
+                    if let Some(sort) = sort {
+                        sort_slots(&mut slots, sort);
+                    }
+                    if let Some(path) = output_file {
+                        if let Err(e) = write_slots_file(path, &slots, format == Some("csv")) {
+                            eprintln!("Failed to write {}: {:#}", path.display(), e);
+                            failed = true;
+                        }
+                    }
+                    if format == Some("csv") {
+                        if let Err(e) = view_utils::write_csv(&slots, &mut io::stdout()) {
+                            eprintln!("Failed to write slots: {}", e);
+                            failed = true;
+                        }
+                    } else if json {
+                        match serde_json::to_string_pretty(&slots) {
+                            Ok(json_string) => println!("{}", json_string),
+                            Err(e) => {
+                                eprintln!("Failed to serialize slots: {}", e);
+                                failed = true;
+                            }
+                        }
+                    } else {
+                        if !quiet {
+                            println!("venue details loaded successfully");
+                        }
+                        view_utils::print_table(&slots, raw, color, plain);
+                    }
                 },
-                Err(e) => println!("Failed to load venue details: {}", e),
+                (Err(ResyClientError::AuthExpired), _) => {
+                    report_failure(quiet, AUTH_EXPIRED_MESSAGE);
+                    failed = true;
+                }
+                (Err(e), _) => {
+                    report_failure(quiet, format!("Failed to load venue details: {}", e));
+                    failed = true;
+                }
+            }
+        }
+        Some(("find", sub_matches)) => {
+            let query = sub_matches.get_one::<String>("query").unwrap();
+
+            if let Some(location) = sub_matches.get_one::<String>("location") {
+                resy_client.config.location = location.clone();
+            }
+
+            match resy_client.search_venues(query).await {
+                Ok(venues) if venues.is_empty() => println!("No venues found for '{}'", query),
+                Ok(venues) => {
+                    for (i, venue) in venues.iter().enumerate() {
+                        println!("{:>2}. {} ({}) - {}", i + 1, venue.name, venue.slug, venue.neighborhood);
+                    }
+
+                    let mut input_string = String::new();
+                    println!(">> Select a venue (number, blank to skip): ");
+                    io::stdout().flush().expect("Failed to flush stdout");
+                    io::stdin().read_line(&mut input_string).expect("Failed to read line");
+
+                    match input_string.trim().parse::<usize>() {
+                        Ok(n) if n >= 1 && n <= venues.len() => {
+                            let venue = &venues[n - 1];
+                            resy_client.use_venue(venue);
+                            println!("Selected {} (venue id: {})", venue.name, venue.venue_id);
+                        }
+                        _ => println!("No venue selected"),
+                    }
+                }
+                Err(ResyClientError::AuthExpired) => {
+                    report_failure(quiet, AUTH_EXPIRED_MESSAGE);
+                    failed = true;
+                }
+                Err(e) => {
+                    report_failure(quiet, format!("Failed to search venues: {}", e));
+                    failed = true;
+                }
             }
         }
         Some(("load", sub_matches)) => {
+            if sub_matches.get_flag("keyring") {
+                resy_client.config.use_keyring = true;
+            }
+
             if !sub_matches.get_flag("skip") {
                 let mut input_string = String::new();
                 println!(">> Enter API Key: ");
@@ -171,22 +953,146 @@ async fn main() -> Result<()> {
                 io::stdin().read_line(&mut input_string).expect("Failed to read line");
                 let auth_token = input_string.trim().to_string().clone();
 
-                resy_client.config.api_key = api_key;
-                resy_client.config.auth_token = auth_token;
+                resy_client.update_auth(api_key, auth_token);
 
                 println!("Successfully loaded .marksman.config!");
             }
 
-            match resy_client.get_payment_id().await {
-                Ok(payment_id) => println!("Payment id found: {}", payment_id),
-                Err(e) => println!("Failed to load payment_id: {}", e),
+            if sub_matches.get_flag("select") {
+                match resy_client.get_payment_methods().await {
+                    Ok(methods) if methods.is_empty() => {
+                        report_failure(quiet, "No payment methods found in resy account");
+                        failed = true;
+                    }
+                    Ok(methods) => {
+                        for (i, method) in methods.iter().enumerate() {
+                            let marker = if method.is_default { " (default)" } else { "" };
+                            println!("{:>2}. {} ending in {}{}", i + 1, method.method_type, method.last4, marker);
+                        }
+
+                        let mut input_string = String::new();
+                        println!(">> Select a payment method (number): ");
+                        io::stdout().flush().expect("Failed to flush stdout");
+                        io::stdin().read_line(&mut input_string).expect("Failed to read line");
+
+                        match input_string.trim().parse::<usize>() {
+                            Ok(n) if n >= 1 && n <= methods.len() => {
+                                resy_client.config.payment_id = methods[n - 1].id.clone();
+                                println!("Payment id set: {}", resy_client.config.payment_id);
+                            }
+                            _ => {
+                                report_failure(quiet, "No payment method selected");
+                                failed = true;
+                            }
+                        }
+                    }
+                    Err(ResyClientError::AuthExpired) => {
+                        report_failure(quiet, AUTH_EXPIRED_MESSAGE);
+                        failed = true;
+                    }
+                    Err(e) => {
+                        report_failure(quiet, format!("Failed to load payment methods: {}", e));
+                        failed = true;
+                    }
+                }
+            } else {
+                match resy_client.get_payment_id().await {
+                    Ok(payment_id) => println!("Payment id found: {}", payment_id),
+                    Err(ResyClientError::AuthExpired) => {
+                        report_failure(quiet, AUTH_EXPIRED_MESSAGE);
+                        failed = true;
+                    }
+                    Err(e) => {
+                        report_failure(quiet, format!("Failed to load payment_id: {}", e));
+                        failed = true;
+                    }
+                }
+            }
+
+        }
+        Some(("login", sub_matches)) => {
+            if let Some(api_key) = sub_matches.get_one::<String>("api-key") {
+                let auth_token = resy_client.config.auth_token.clone();
+                resy_client.update_auth(api_key.clone(), auth_token);
+            }
+
+            let email = sub_matches.get_one::<String>("email").unwrap();
+            // never taken as a flag, where it would end up in shell history and `ps`
+            let password = match env::var(PASSWORD_ENV_VAR) {
+                Ok(password) => password,
+                Err(_) => rpassword::prompt_password(">> Enter Resy password: ").expect("Failed to read password"),
+            };
+
+            let mut result = resy_client.login(email, &password, None).await;
+            if let Ok(LoginOutcome::OtpRequired) = result {
+                let mut input_string = String::new();
+                println!(">> Enter the code Resy sent you: ");
+                io::stdout().flush().expect("Failed to flush stdout");
+                io::stdin().read_line(&mut input_string).expect("Failed to read line");
+                result = resy_client.login(email, &password, Some(input_string.trim())).await;
             }
 
+            match result {
+                Ok(LoginOutcome::LoggedIn) => println!("Logged in, auth token saved"),
+                Ok(LoginOutcome::OtpRequired) => {
+                    report_failure(quiet, "Login failed: Resy is still asking for a one-time code");
+                    failed = true;
+                }
+                Err(e) => {
+                    report_failure(quiet, format!("Login failed: {}", e));
+                    failed = true;
+                }
+            }
         }
         Some(("state", _)) => {
             match serde_json::to_string_pretty(&resy_client.config) {
                 Ok(json_string) => println!("Current Configuration:\n{}", json_string),
-                Err(e) => println!("Failed to serialize config: {}", e),
+                Err(e) => {
+                    report_failure(quiet, format!("Failed to serialize config: {}", e));
+                    failed = true;
+                }
+            }
+        }
+        Some(("history", sub_matches)) => {
+            match history::read_all(&data_dir) {
+                Ok(entries) => {
+                    let include_failures = sub_matches.get_flag("failures");
+                    let entries: Vec<_> = entries.into_iter()
+                        .filter(|entry| entry.success || include_failures)
+                        .collect();
+
+                    if entries.is_empty() {
+                        println!("No bookings recorded yet");
+                    } else {
+                        view_utils::print_history(&entries);
+                    }
+                }
+                Err(e) => {
+                    report_failure(quiet, format!("Failed to read history: {:#}", e));
+                    failed = true;
+                }
+            }
+        }
+        Some(("stats", _)) => {
+            match stats::read_all(&data_dir) {
+                Ok(entries) if entries.is_empty() => println!("No snipes recorded yet"),
+                Ok(entries) => view_utils::print_stats(&entries),
+                Err(e) => {
+                    report_failure(quiet, format!("Failed to read stats: {:#}", e));
+                    failed = true;
+                }
+            }
+        }
+        Some(("validate", _)) => {
+            let errors = resy_client.config.booking_validation_errors();
+            if errors.is_empty() {
+                println!("Configuration is valid");
+            } else {
+                report_failure(quiet, "Configuration is invalid:");
+                for error in &errors {
+                    report_failure(quiet, format!("  - {}", error));
+                }
+                failed = true;
             }
         }
         Some(("snipe", sub_matches)) => {
@@ -195,19 +1101,330 @@ async fn main() -> Result<()> {
 
             // Determine the date based on input
             let formatted_date = match snipe_date {
-                Some("today") => Local::now().format("%Y-%m-%d").to_string(),
-                Some("tmrw") => (Local::now() + Duration::days(1)).format("%Y-%m-%d").to_string(),
+                Some("today") => resy_client.clock().now_local().format("%Y-%m-%d").to_string(),
+                Some("tmrw") => config::default_snipe_date(resy_client.clock()),
                 _ => snipe_date.unwrap_or_default().to_string(),
             };
 
-            match resy_client.run_sniper(snipe_time, &formatted_date).await {
-                Ok(tok) => println!("Successful booking! (token: {:#?})", tok),
-                Err(e) => println!("Snipe failed with {}", e)
+            if let Some(fill_preference) = sub_matches.get_one::<FillPreference>("fill-preference") {
+                resy_client.config.fill_preference = Some(*fill_preference);
+            }
+
+            if let Some(seating) = sub_matches.get_one::<String>("seating") {
+                resy_client.config.seating_preference = Some(seating.clone());
+            }
+
+            if let Some(earliest) = sub_matches.get_one::<String>("earliest") {
+                resy_client.config.earliest_time = Some(earliest.clone());
+            }
+
+            if let Some(latest) = sub_matches.get_one::<String>("latest") {
+                resy_client.config.latest_time = Some(latest.clone());
+            }
+
+            if let Some(fallback_sizes) = sub_matches.get_many::<u8>("fallback-sizes") {
+                resy_client.config.fallback_party_sizes = fallback_sizes.copied().collect();
+            }
+
+            if let Some(dates) = sub_matches.get_many::<String>("dates") {
+                resy_client.config.acceptable_dates = dates.cloned().collect();
+                // the first date stands in wherever a single date is needed
+                resy_client.config.date = resy_client.config.acceptable_dates[0].clone();
+            }
+
+            let options = SnipeOptions {
+                countdown: sub_matches.get_flag("countdown") && !quiet,
+                slot_rank: sub_matches.get_one::<u64>("slot-rank").copied().unwrap_or(1) as usize,
+                token: sub_matches.get_one::<String>("token").cloned(),
+                sync_clock: sub_matches.get_flag("sync-clock"),
+                notify: sub_matches.get_flag("notify"),
+                webhook_url: sub_matches.get_one::<String>("webhook").cloned(),
+                force_large: sub_matches.get_flag("force-large"),
+                repeat: *sub_matches.get_one::<u32>("repeat").unwrap(),
+                repeat_interval_mins: *sub_matches.get_one::<u64>("interval").unwrap(),
+                force: sub_matches.get_flag("force"),
+                slots_file: sub_matches.get_one::<PathBuf>("from-file").cloned(),
+                all_accounts: sub_matches.get_one::<String>("accounts").is_some(),
+                venues: sub_matches.get_many::<String>("venues").map(|venues| venues.cloned().collect()).unwrap_or_default(),
+                confirm: sub_matches.get_flag("confirm"),
+                book_first: sub_matches.get_flag("book-first"),
+                ..SnipeOptions::default()
+            };
+
+            if options.all_accounts && resy_client.config.accounts.is_empty() && !quiet {
+                println!("WARNING: --accounts all was given but no extra accounts are configured; sniping from the main account only");
+            }
+
+            if let Some(slots_file) = options.slots_file.as_ref().filter(|_| !quiet) {
+                println!("WARNING: booking from saved slots in {}; tokens may be stale by snipe time", slots_file.display());
+            }
+
+            if sub_matches.get_flag("dry-run") {
+                match resy_client.run_dry_sniper(snipe_time, &formatted_date, &options).await {
+                    Ok(slot) if quiet => println!("{}", slot.token),
+                    Ok(slot) => println!("DRY RUN - no booking made (slot: {}, token: {})", slot.start, slot.token),
+                    Err(ResyClientError::AuthExpired) => {
+                        report_failure(quiet, AUTH_EXPIRED_MESSAGE);
+                        failed = true;
+                    }
+                    Err(ResyClientError::Cancelled) => {
+                        report_failure(quiet, "Snipe cancelled by user");
+                        cancelled = true;
+                    }
+                    Err(e) => {
+                        report_failure(quiet, format!("Dry run failed with {}", e));
+                        failed = true;
+                    }
+                }
+            } else {
+                match resy_client.run_sniper(snipe_time, &formatted_date, &options).await {
+                    Ok(outcome) => {
+                        // persist the booking right away rather than waiting for the final write
+                        config::write_config(&resy_client.config, Some(&config_path)).context("Failed to write config")?;
+                        if quiet {
+                            println!("{}", outcome)
+                        } else if outcome.slot_type.is_empty() {
+                            println!("Successful booking! {} for {} (token: {})", outcome.start, outcome.party_size, outcome)
+                        } else {
+                            println!("Successful booking! {} @ {} for {} (token: {})", outcome.slot_type, outcome.start, outcome.party_size, outcome)
+                        }
+                    },
+                    Err(ResyClientError::AuthExpired) => {
+                        report_failure(quiet, AUTH_EXPIRED_MESSAGE);
+                        failed = true;
+                    }
+                    Err(ResyClientError::Cancelled) => {
+                        report_failure(quiet, "Snipe cancelled by user");
+                        cancelled = true;
+                    }
+                    Err(e) => {
+                        report_failure(quiet, format!("Snipe failed with {}", e));
+                        failed = true;
+                    }
+                }
+
+                let metrics = resy_client.last_snipe_metrics();
+                if metrics.fired() && !quiet {
+                    println!("{}", metrics);
+                }
+            }
+        }
+        Some(("schedule", sub_matches)) => {
+            let dining_date = sub_matches.get_one::<String>("date").unwrap();
+            let days_before = *sub_matches.get_one::<u32>("days-before").unwrap();
+            let drop_time = sub_matches.get_one::<String>("drop-time").unwrap();
+
+            let options = SnipeOptions {
+                countdown: sub_matches.get_flag("countdown") && !quiet,
+                ..SnipeOptions::default()
+            };
+
+            match resy_client.run_scheduled_sniper(dining_date, days_before, drop_time, &options).await {
+                Ok(outcome) => {
+                    config::write_config(&resy_client.config, Some(&config_path)).context("Failed to write config")?;
+                    if quiet {
+                        println!("{}", outcome)
+                    } else {
+                        println!("Successful booking! {} @ {} for {} (token: {})", outcome.slot_type, outcome.start, outcome.party_size, outcome)
+                    }
+                },
+                Err(ResyClientError::AuthExpired) => {
+                    report_failure(quiet, AUTH_EXPIRED_MESSAGE);
+                    failed = true;
+                }
+                Err(ResyClientError::Cancelled) => {
+                    report_failure(quiet, "Snipe cancelled by user");
+                    cancelled = true;
+                }
+                Err(e) => {
+                    report_failure(quiet, format!("Scheduled snipe failed with {}", e));
+                    failed = true;
+                }
+            }
+
+            let metrics = resy_client.last_snipe_metrics();
+            if metrics.fired() && !quiet {
+                println!("{}", metrics);
+            }
+        }
+        Some(("watch", sub_matches)) => {
+            let options = WatchOptions {
+                interval: std::time::Duration::from_secs(*sub_matches.get_one::<u64>("interval").unwrap()),
+                max_duration: sub_matches.get_one::<f64>("max-duration")
+                    .map(|hours| std::time::Duration::from_secs_f64(hours.max(0.0) * 3600.0)),
+                auto_book: sub_matches.get_flag("auto-book"),
+            };
+
+            if !quiet {
+                println!("Watching {} on {} for a party of {} (Ctrl-C to stop)", resy_client.config.venue_slug, resy_client.config.date, resy_client.config.party_size);
+            }
+            match resy_client.watch(&options).await {
+                Ok(WatchOutcome::Found(slots)) => {
+                    if !quiet {
+                        // terminal bell so a watch left running in the background is noticed
+                        print!("\x07");
+                        println!("Availability found!");
+                    }
+                    let terminal = io::stdout().is_terminal();
+                    view_utils::print_table(&slots, false, terminal, !terminal);
+                }
+                Ok(WatchOutcome::Booked(outcome)) => {
+                    config::write_config(&resy_client.config, Some(&config_path)).context("Failed to write config")?;
+                    if quiet {
+                        println!("{}", outcome)
+                    } else {
+                        println!("Successful booking! {} @ {} for {} (token: {})", outcome.slot_type, outcome.start, outcome.party_size, outcome)
+                    }
+                }
+                Err(ResyClientError::AuthExpired) => {
+                    report_failure(quiet, AUTH_EXPIRED_MESSAGE);
+                    failed = true;
+                }
+                Err(ResyClientError::Cancelled) => {
+                    report_failure(quiet, "Watch stopped by user");
+                    cancelled = true;
+                }
+                Err(e) => {
+                    report_failure(quiet, format!("Watch ended without a booking: {}", e));
+                    failed = true;
+                }
+            }
+        }
+        Some(("payment", sub_matches)) => {
+            let result = match sub_matches.subcommand() {
+                Some(("set", payment_matches)) => {
+                    let id = payment_matches.get_one::<String>("id").unwrap();
+                    resy_client.set_payment_id(id).await.map(|method| {
+                        println!("Payment id set: {} ({} ending in {})", method.id, method.method_type, method.last4);
+                    })
+                }
+                Some(("prefer", payment_matches)) => {
+                    let preference = payment_matches.get_one::<String>("preference").unwrap();
+                    resy_client.set_payment_preference(preference).await.map(|payment_id| {
+                        println!("Payment preference '{}' set, booking with payment id {}", preference, payment_id);
+                    })
+                }
+                _ => resy_client.get_payment_methods().await.map(|methods| {
+                    for method in methods {
+                        let marker = if method.id == resy_client.config.payment_id { "*" } else { " " };
+                        let default = if method.is_default { " (default)" } else { "" };
+                        println!("{} {}: {} ending in {}{}", marker, method.id, method.method_type, method.last4, default);
+                    }
+                }),
+            };
+
+            match result {
+                Ok(_) => {}
+                Err(ResyClientError::AuthExpired) => {
+                    report_failure(quiet, AUTH_EXPIRED_MESSAGE);
+                    failed = true;
+                }
+                Err(e) => {
+                    report_failure(quiet, format!("Failed to load payment methods: {}", e));
+                    failed = true;
+                }
+            }
+        }
+        Some(("profile", sub_matches)) => {
+            match sub_matches.subcommand() {
+                Some(("add", profile_matches)) => {
+                    let name = profile_matches.get_one::<String>("name").unwrap();
+                    resy_client.config.save_profile(name);
+                    println!("Saved profile '{}'", name);
+                }
+                Some(("use", profile_matches)) => {
+                    let name = profile_matches.get_one::<String>("name").unwrap();
+                    match resy_client.config.use_profile(name) {
+                        Ok(_) => println!("Switched to profile '{}'", name),
+                        Err(e) => {
+                            report_failure(quiet, format!("Failed to switch profile: {}", e));
+                            failed = true;
+                        }
+                    }
+                }
+                Some(("list", _)) => {
+                    let mut names: Vec<&String> = resy_client.config.profiles.keys().collect();
+                    names.sort();
+
+                    if names.is_empty() {
+                        println!("No saved profiles");
+                    }
+                    for name in names {
+                        let profile = &resy_client.config.profiles[name];
+                        let marker = if resy_client.config.active_profile.as_ref() == Some(name) { "*" } else { " " };
+                        println!("{} {} ({}, {}, party of {})", marker, name, profile.venue_slug, profile.date, profile.party_size);
+                    }
+                }
+                Some(("rm", profile_matches)) => {
+                    let name = profile_matches.get_one::<String>("name").unwrap();
+                    if resy_client.config.remove_profile(name) {
+                        println!("Removed profile '{}'", name);
+                    } else {
+                        println!("No profile named '{}'", name);
+                    }
+                }
+                _ => {}
+            }
+        }
+        Some(("set", sub_matches)) => {
+            let key = sub_matches.get_one::<String>("key").unwrap();
+            let value = sub_matches.get_one::<String>("value").unwrap();
+
+            match resy_client.set_config_value(key, value).await {
+                Ok(_) => println!("Set {} to {}", key, value),
+                Err(ResyClientError::AuthExpired) => {
+                    report_failure(quiet, AUTH_EXPIRED_MESSAGE);
+                    failed = true;
+                }
+                Err(e) => {
+                    report_failure(quiet, format!("Failed to set {}: {}", key, e));
+                    failed = true;
+                }
+            }
+        }
+        Some(("reset", sub_matches)) => {
+            let all = sub_matches.get_flag("all");
+
+            let confirmed = sub_matches.get_flag("yes") || {
+                let scope = if all { "everything, including credentials" } else { "everything except credentials" };
+                let mut input_string = String::new();
+                println!(">> Reset {} in {}? [y/N]: ", scope, config_path.display());
+                io::stdout().flush().expect("Failed to flush stdout");
+                io::stdin().read_line(&mut input_string).expect("Failed to read line");
+                input_string.trim().eq_ignore_ascii_case("y")
+            };
+
+            if !confirmed {
+                println!("Reset aborted");
+            } else {
+                let fresh = if all { config::Config::default() } else { resy_client.config.credentials_only() };
+                match config::reset(&config_path) {
+                    Ok(_) => {
+                        resy_client.load_config(fresh);
+                        if all {
+                            println!("Reset all settings and credentials to defaults");
+                        } else {
+                            println!("Reset venue, date, party size, times, profiles and other settings to defaults; kept api key, auth token, payment id and accounts");
+                        }
+                    }
+                    Err(e) => {
+                        report_failure(quiet, format!("Failed to reset config: {:#}", e));
+                        failed = true;
+                    }
+                }
             }
         }
         _ => {} // handle new commands
     }
 
+    resy_client.config.sync_active_profile();
     config::write_config(&resy_client.config, Some(&config_path)).context("Failed to write config")?;
+
+    if failed {
+        std::process::exit(1);
+    }
+    if cancelled {
+        std::process::exit(CANCELLED_EXIT_CODE);
+    }
     Ok(())
 }
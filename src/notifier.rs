@@ -0,0 +1,19 @@
+// notifier.rs
+use std::io::{self, Write};
+use log::debug;
+use notify_rust::Notification;
+
+/// Shows a desktop notification, ringing the terminal bell instead where notifications aren't supported.
+pub fn notify(summary: &str, body: &str) {
+    let result = Notification::new()
+        .appname("marksman")
+        .summary(summary)
+        .body(body)
+        .show();
+
+    if let Err(e) = result {
+        debug!("Desktop notification unavailable, ringing bell: {}", e);
+        print!("\x07");
+        let _ = io::stdout().flush();
+    }
+}
@@ -0,0 +1,53 @@
+// ntp.rs
+use std::io;
+use std::net::UdpSocket;
+use std::time::Duration as StdDuration;
+use chrono::{DateTime, Duration, Utc};
+
+const NTP_PORT: u16 = 123;
+const NTP_PACKET_SIZE: usize = 48;
+const NTP_TIMEOUT: StdDuration = StdDuration::from_secs(2);
+// seconds between the NTP epoch (1900) and the unix epoch (1970)
+const NTP_UNIX_EPOCH_DELTA: i64 = 2_208_988_800;
+
+/// Measures how far the local clock is behind the NTP server (true time = local time + offset).
+pub async fn query_offset(server: &str) -> io::Result<Duration> {
+    let server = server.to_string();
+    tokio::task::spawn_blocking(move || query_offset_blocking(&server))
+        .await
+        .map_err(io::Error::other)?
+}
+
+fn query_offset_blocking(server: &str) -> io::Result<Duration> {
+    let socket = UdpSocket::bind("0.0.0.0:0")?;
+    socket.set_read_timeout(Some(NTP_TIMEOUT))?;
+    socket.connect((server, NTP_PORT))?;
+
+    // LI = 0, VN = 3, Mode = 3 (client)
+    let mut packet = [0u8; NTP_PACKET_SIZE];
+    packet[0] = 0x1B;
+
+    let sent_at = Utc::now();
+    socket.send(&packet)?;
+    let received = socket.recv(&mut packet)?;
+    let received_at = Utc::now();
+
+    if received < NTP_PACKET_SIZE {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "short NTP response"));
+    }
+
+    let server_received = read_timestamp(&packet[32..40])
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "invalid NTP receive timestamp"))?;
+    let server_sent = read_timestamp(&packet[40..48])
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "invalid NTP transmit timestamp"))?;
+
+    Ok(((server_received - sent_at) + (server_sent - received_at)) / 2)
+}
+
+fn read_timestamp(bytes: &[u8]) -> Option<DateTime<Utc>> {
+    let seconds = u32::from_be_bytes(bytes[..4].try_into().ok()?) as i64;
+    let fraction = u32::from_be_bytes(bytes[4..8].try_into().ok()?) as i64;
+    let nanos = (fraction * 1_000_000_000) >> 32;
+
+    DateTime::from_timestamp(seconds - NTP_UNIX_EPOCH_DELTA, nanos as u32)
+}
@@ -1,19 +1,85 @@
 use std::error::Error;
-use reqwest::{Client, Response};
-use reqwest::header::{ACCEPT, ACCEPT_LANGUAGE, AUTHORIZATION, CONTENT_TYPE, HeaderMap, HeaderValue};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use chrono::{DateTime, Utc};
+use log::debug;
+use reqwest::{Client, Method, Proxy, RequestBuilder, Response, StatusCode, Url};
+use reqwest::header::{ACCEPT, ACCEPT_LANGUAGE, AUTHORIZATION, CONTENT_TYPE, HeaderMap, HeaderValue, RETRY_AFTER};
+use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
+use tokio::time::sleep;
+use crate::clock::{Clock, SystemClock};
 
 const RESY_API_BASE_URL: &str = "https://api.resy.com";
+const DEFAULT_USER_AGENT: &str = "Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/124.0.0.0 Safari/537.36";
+
+/// Gateway results are `Send` so calls can run inside spawned sniper tasks.
+pub type APIResult = Result<Value, Box<dyn Error + Send + Sync>>;
+
+const DEFAULT_MAX_RETRIES: u32 = 5;
+const RETRY_BASE_DELAY_MS: u64 = 50;
+const RETRY_MAX_DELAY_MS: u64 = 800;
+// a Retry-After longer than this would outlast any drop, so it is clamped
+const RETRY_AFTER_MAX_SECS: u64 = 30;
+const DEFAULT_REQUEST_TIMEOUT_MS: u64 = 5000;
+const DEFAULT_CONNECT_TIMEOUT_MS: u64 = 2000;
+
+/// How the `/3/book` request body is encoded; Resy has switched between the two before.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum BookBodyFormat {
+    /// `book_token=...&struct_payment_method={"id":...}`, what the Resy widget sends today
+    #[default]
+    FormUrlencoded,
+    /// `{"book_token": ..., "struct_payment_method": {"id": ...}}`
+    Json,
+}
 
 /// Error type for Resy API specific errors.
 #[derive(Debug)]
-pub struct ResyAPIError {
-    pub message: String,
+pub enum ResyAPIError {
+    /// The auth token was rejected (401/419) and needs to be refreshed.
+    AuthExpired(StatusCode),
+    /// The request (or connecting) took longer than the configured timeout.
+    Timeout,
+    /// A successful status whose body was not JSON (e.g. an HTML error page), with the start of the body.
+    InvalidResponse {
+        status: StatusCode,
+        snippet: String,
+    },
+    /// Any other failed request, with the HTTP status when one was received and the wait the
+    /// API asked for in a `Retry-After` header.
+    Request {
+        message: String,
+        status: Option<StatusCode>,
+        retry_after: Option<Duration>,
+    },
+}
+
+impl ResyAPIError {
+    /// Whether the request is worth retrying (rate limited or transient server error).
+    pub fn is_retryable(&self) -> bool {
+        matches!(
+            self,
+            ResyAPIError::Request {
+                status: Some(StatusCode::TOO_MANY_REQUESTS)
+                    | Some(StatusCode::INTERNAL_SERVER_ERROR)
+                    | Some(StatusCode::BAD_GATEWAY)
+                    | Some(StatusCode::SERVICE_UNAVAILABLE),
+                ..
+            }
+        )
+    }
 }
 
 impl std::fmt::Display for ResyAPIError {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        write!(f, "{}", self.message)
+        match self {
+            ResyAPIError::AuthExpired(status) => write!(f, "Auth token rejected: {}", status),
+            ResyAPIError::Timeout => write!(f, "Request timed out"),
+            ResyAPIError::InvalidResponse { status, snippet } => write!(f, "Response ({}) was not valid JSON: {}", status, snippet),
+            ResyAPIError::Request { message, .. } => write!(f, "{}", message),
+        }
     }
 }
 
@@ -21,58 +87,213 @@ impl Error for ResyAPIError {}
 
 impl From<std::io::Error> for ResyAPIError {
     fn from(error: std::io::Error) -> Self {
-        ResyAPIError {
+        ResyAPIError::Request {
             message: error.to_string(),
+            status: None,
+            retry_after: None,
         }
     }
 }
 
 /// Handles communication with the Resy API.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct ResyAPIGateway {
-    client: Client,
+    // shared by clones, so every copy of the gateway draws on one connection pool
+    client: Arc<Client>,
     api_key: String,
     auth_token: String,
+    max_retries: u32,
+    base_url: String,
+    request_timeout: Duration,
+    connect_timeout: Duration,
+    proxy: Option<Proxy>,
+    user_agent: Option<String>,
+    book_body_format: BookBodyFormat,
+    clock: Arc<dyn Clock>,
+}
+
+impl Default for ResyAPIGateway {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl ResyAPIGateway {
 
     /// Creates a new API gateway instance (without authentication)
     pub fn new() -> Self {
-        ResyAPIGateway {
-            client: Client::new(),
-            api_key: String::new(),
-            auth_token: String::new(),
-        }
+        Self::from_auth(String::new(), String::new())
     }
 
     /// Creates a new API gateway instance with authentication.
     pub fn from_auth(api_key: String, auth_token: String) -> Self {
+        let request_timeout = Duration::from_millis(DEFAULT_REQUEST_TIMEOUT_MS);
+        let connect_timeout = Duration::from_millis(DEFAULT_CONNECT_TIMEOUT_MS);
+
         ResyAPIGateway {
-            client: Client::new(),
+            client: Arc::new(build_client(request_timeout, connect_timeout, None).expect("Failed to build HTTP client")),
             api_key,
             auth_token,
+            max_retries: DEFAULT_MAX_RETRIES,
+            base_url: RESY_API_BASE_URL.to_string(),
+            request_timeout,
+            connect_timeout,
+            proxy: None,
+            user_agent: None,
+            book_body_format: BookBodyFormat::default(),
+            clock: Arc::new(SystemClock),
         }
     }
 
+    /// Sets how many times booking calls are retried on transient failures.
+    pub fn with_max_retries(mut self, max_retries: u32) -> Self {
+        self.max_retries = max_retries;
+        self
+    }
+
+    /// Points requests at another API host (a mock server, staging, or a debugging proxy).
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into().trim_end_matches('/').to_string();
+        self
+    }
+
+    /// Routes every request (warmup, discovery and booking alike) through an HTTP or SOCKS proxy.
+    pub fn with_proxy(mut self, proxy_url: &str) -> Result<Self, ResyAPIError> {
+        let invalid = |e: reqwest::Error| ResyAPIError::Request {
+            message: format!("Invalid proxy url '{}': {}", proxy_url, e),
+            status: None,
+            retry_after: None,
+        };
+
+        let proxy = Proxy::all(proxy_url).map_err(invalid)?;
+        self.client = Arc::new(build_client(self.request_timeout, self.connect_timeout, Some(&proxy)).map_err(invalid)?);
+        self.proxy = Some(proxy);
+        Ok(self)
+    }
+
+    /// Bounds each request and its connection setup so a hung connection can't stall a snipe.
+    pub fn with_timeouts(mut self, request_timeout: Duration, connect_timeout: Duration) -> Self {
+        self.client = Arc::new(build_client(request_timeout, connect_timeout, self.proxy.as_ref()).expect("Failed to build HTTP client"));
+        self.request_timeout = request_timeout;
+        self.connect_timeout = connect_timeout;
+        self
+    }
+
+    /// Swaps the credentials used for subsequent requests. The `Client` is kept, so its
+    /// connection pool (and any warmed keep-alive connections) survives re-auth.
+    pub fn set_auth(&mut self, api_key: String, auth_token: String) {
+        self.api_key = api_key;
+        self.auth_token = auth_token;
+    }
+
+    /// Whether both gateways send through the same `Client`, and so share its connection pool.
+    pub fn shares_client_with(&self, other: &ResyAPIGateway) -> bool {
+        Arc::ptr_eq(&self.client, &other.client)
+    }
+
+    pub fn set_max_retries(&mut self, max_retries: u32) {
+        self.max_retries = max_retries;
+    }
+
+    pub fn set_book_body_format(&mut self, book_body_format: BookBodyFormat) {
+        self.book_body_format = book_body_format;
+    }
+
+    /// Overrides the browser user-agent (and the matching `sec-ch-ua-platform` hint). `None` restores the default.
+    pub fn set_user_agent(&mut self, user_agent: Option<String>) {
+        self.user_agent = user_agent;
+    }
+
+    /// Replaces the clock an HTTP-date `Retry-After` is measured against (the system clock by default).
+    pub fn set_clock(&mut self, clock: Arc<dyn Clock>) {
+        self.clock = clock;
+    }
+
+    /// Like [`ResyAPIGateway::with_base_url`], for an existing gateway. `None` restores the real Resy API.
+    pub fn set_base_url(&mut self, base_url: Option<&str>) {
+        self.base_url = base_url.unwrap_or(RESY_API_BASE_URL).trim_end_matches('/').to_string();
+    }
+
     /// Processes the HTTP response, converting JSON or returning an error.
-    async fn process_response(response: Response) -> Result<Value, Box<dyn Error>> {
-        if response.status().is_success() {
-            let json = response.json().await?;
-            Ok(json)
+    async fn process_response(response: Response, method: &Method, url: &Url, started: Instant, now: DateTime<Utc>) -> APIResult {
+        let status = response.status();
+        debug!("{} {} -> {} in {}ms", method, url.path(), status, started.elapsed().as_millis());
+
+        if status.is_success() {
+            // read the text first so a non-JSON body (e.g. a maintenance page) can be shown in the error
+            let body = response.text().await.map_err(send_error)?;
+            serde_json::from_str(&body).map_err(|_| {
+                Box::new(ResyAPIError::InvalidResponse { status, snippet: body_snippet(&body) }) as Box<dyn Error + Send + Sync>
+            })
+        } else if status == StatusCode::UNAUTHORIZED || status.as_u16() == 419 {
+            Err(Box::new(ResyAPIError::AuthExpired(status)))
         } else {
-            Err(Box::new(ResyAPIError {
-                message: format!("API request failed: {}", response.status())
+            let retry_after = response.headers().get(RETRY_AFTER)
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| parse_retry_after(value, now));
+            // keep Resy's explanation (e.g. "reservation no longer available") when it sends one
+            let body = response.text().await.unwrap_or_default();
+            let message = match error_reason(&body) {
+                Some(reason) => format!("API request failed: {}: {}", status, reason),
+                None => format!("API request failed: {}", status),
+            };
+            Err(Box::new(ResyAPIError::Request {
+                message,
+                status: Some(status),
+                retry_after,
             }))
         }
     }
 
+    /// Sends a single request, timing it for the debug log.
+    async fn send(&self, request: RequestBuilder) -> APIResult {
+        let request = request.build().map_err(send_error)?;
+        let method = request.method().clone();
+        let url = request.url().clone();
+
+        let started = Instant::now();
+        let response = self.client.execute(request).await.map_err(send_error)?;
+        Self::process_response(response, &method, &url, started, self.clock.now_utc()).await
+    }
+
+    /// Sends the request, retrying while the API returns a retryable status. Waits as long as a
+    /// `Retry-After` header asks, otherwise backs off exponentially.
+    async fn send_with_retry<F>(&self, build_request: F) -> APIResult
+    where
+        F: Fn() -> RequestBuilder,
+    {
+        let mut attempt = 0;
+        loop {
+            let result = self.send(build_request()).await;
+
+            let error = match &result {
+                Err(e) => e.downcast_ref::<ResyAPIError>().filter(|e| e.is_retryable()),
+                Ok(_) => None,
+            };
+            let Some(error) = error.filter(|_| attempt < self.max_retries) else {
+                return result;
+            };
+
+            let delay = match error {
+                ResyAPIError::Request { retry_after: Some(retry_after), .. } => {
+                    debug!("Rate limited, server asked to wait {}ms", retry_after.as_millis());
+                    *retry_after
+                }
+                _ => Duration::from_millis((RETRY_BASE_DELAY_MS << attempt).min(RETRY_MAX_DELAY_MS)),
+            };
+            debug!("Retrying request in {}ms (attempt {}/{})", delay.as_millis(), attempt + 1, self.max_retries);
+            sleep(delay).await;
+            attempt += 1;
+        }
+    }
+
     /// Sets up the necessary auth headers for making requests to the Resy API.
-    fn setup_headers(&self) -> HeaderMap {
+    /// Only the content type differs between JSON and form requests.
+    fn build_headers(&self, content_type: &str) -> HeaderMap {
         let mut headers = HeaderMap::new();
 
-        // ??
-        headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+        // content
+        headers.insert(CONTENT_TYPE, HeaderValue::from_str(content_type).unwrap());
         headers.insert(ACCEPT, HeaderValue::from_static("application/json, text/plain, */*"));
         headers.insert(ACCEPT_LANGUAGE, HeaderValue::from_static("en-US,en;q=0.9"));
 
@@ -89,59 +310,91 @@ impl ResyAPIGateway {
         headers.insert("referer", HeaderValue::from_static("https://widgets.resy.com/"));
         headers.insert("sec-ch-ua", HeaderValue::from_static("\"Not-A.Brand\";v=\"99\", \"Chromium\";v=\"124\""));
         headers.insert("sec-ch-ua-mobile", HeaderValue::from_static("?0"));
-        headers.insert("sec-ch-ua-platform", HeaderValue::from_static("\"macOS\""));
+        headers.insert("sec-ch-ua-platform", HeaderValue::from_static(self.platform_hint()));
         headers.insert("sec-fetch-dest", HeaderValue::from_static("empty"));
         headers.insert("sec-fetch-mode", HeaderValue::from_static("cors"));
         headers.insert("sec-fetch-site", HeaderValue::from_static("same-site"));
-        headers.insert("user-agent", HeaderValue::from_static("Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/124.0.0.0 Safari/537.36"));
+        headers.insert("user-agent", self.user_agent_header());
         headers.insert("x-origin", HeaderValue::from_static("https://widgets.resy.com"));
 
         headers
     }
 
-    /// Fetches user details from the Resy API.
-    pub async fn get_user(&self) -> Result<Value, Box<dyn Error>> {
-        let url = format!("{}/2/user", RESY_API_BASE_URL);
-        let headers = self.setup_headers();
+    /// Signs in with email and password, plus the one-time code on accounts with 2FA. Sent without
+    /// the current auth token, which is usually the expired one being replaced.
+    pub async fn login(&self, email: &str, password: &str, otp: Option<&str>) -> APIResult {
+        let url = format!("{}/3/auth/password", self.base_url);
+        let mut headers = self.build_headers("application/x-www-form-urlencoded");
+        headers.remove("x-resy-auth-token");
+        headers.remove("x-resy-universal-auth");
+
+        let mut body = format!("email={}&password={}", urlencoding::encode(email), urlencoding::encode(password));
+        if let Some(otp) = otp {
+            body.push_str(&format!("&otp={}", urlencoding::encode(otp)));
+        }
 
-        let res = self.client.get(url)
+        let request = self.client.post(url)
             .headers(headers)
-            .send()
-            .await?;
+            .body(body);
 
-        Self::process_response(res).await
+        self.send(request).await
+    }
+
+    pub async fn get_user(&self) -> APIResult {
+        let url = format!("{}/2/user", self.base_url);
+        let headers = self.build_headers("application/json");
+
+        let request = self.client.get(url)
+            .headers(headers);
+
+        self.send(request).await
     }
 
     /// Retrieves details about a venue from the Resy API.
-    pub async fn get_venue(&self, venue_slug: &str) -> Result<Value, Box<dyn Error>> {
-        let url = format!("{}/3/venue?url_slug={}&location=new-york-ny", RESY_API_BASE_URL, venue_slug);
-        let headers = self.setup_headers();
+    pub async fn get_venue(&self, venue_slug: &str, location: &str) -> APIResult {
+        let url = format!("{}/3/venue?url_slug={}&location={}", self.base_url, venue_slug, location);
+        let headers = self.build_headers("application/json");
+
+        let request = self.client.get(url)
+            .headers(headers);
 
-        let res = self.client.get(url)
+        self.send(request).await
+    }
+
+    /// Searches venues by name within a location.
+    pub async fn search_venues(&self, query: &str, location: &str) -> APIResult {
+        let url = format!("{}/3/venuesearch/search", self.base_url);
+        let headers = self.build_headers("application/json");
+
+        let data = json!({
+            "query": query,
+            "location": location,
+            "types": ["venue"],
+            "per_page": 10
+        });
+
+        let request = self.client.post(url)
             .headers(headers)
-            .send()
-            .await?;
+            .json(&data);
 
-        Self::process_response(res).await
+        self.send(request).await
     }
 
     /// Finds reservations at a venue.
-    pub async fn find_reservation(&self, venue_id: &str, day: &str, party_size: u8, target_time: Option<&str>) -> Result<Value, Box<dyn Error>> {
-        let mut url = format!("{}/4/find?lat=0&long=0&day={}&party_size={}&venue_id={}", RESY_API_BASE_URL, day, party_size, venue_id);
+    pub async fn find_reservation(&self, venue_id: &str, day: &str, party_size: u8, target_time: Option<&str>) -> APIResult {
+        let mut url = format!("{}/4/find?lat=0&long=0&day={}&party_size={}&venue_id={}", self.base_url, day, party_size, venue_id);
 
         if let Some(time) = target_time {
             let formatted_time = format!("{}:{}", &time[..2], &time[2..]);
             url = format!("{}&time_filter={}", url, formatted_time);
         }
 
-        let headers = self.setup_headers();
+        let headers = self.build_headers("application/json");
 
-        let res = self.client.get(url)
-            .headers(headers)
-            .send()
-            .await?;
+        let request = self.client.get(url)
+            .headers(headers);
 
-        Self::process_response(res).await
+        self.send(request).await
     }
 
     /// Gets reservation details from the Resy API.
@@ -151,77 +404,162 @@ impl ResyAPIGateway {
         config_id: &str,
         party_size: u8,
         day: &str,
-    ) -> Result<Value, Box<dyn Error>> {
-        let url = format!("{}/3/details", RESY_API_BASE_URL);
-        let headers = self.setup_headers();
+        source_id: Option<&str>, // required by some venues' widget flow
+    ) -> APIResult {
+        let url = format!("{}/3/details", self.base_url);
+        let headers = self.build_headers("application/json");
 
-        let data = json!({
+        let mut data = json!({
             "commit": commit,
             "config_id": config_id,
             "day": day,
             "party_size": party_size
         });
+        if let Some(source_id) = source_id {
+            data["source_id"] = json!(source_id);
+        }
 
-        let res = self.client.post(url)
-            .headers(headers)
-            .json(&data)
-            .send()
-            .await?;
+        self.send_with_retry(|| {
+            self.client.post(&url)
+                .headers(headers.clone())
+                .json(&data)
+        }).await
+    }
 
-        Self::process_response(res).await
+    fn user_agent_header(&self) -> HeaderValue {
+        self.user_agent.as_deref()
+            .and_then(|user_agent| HeaderValue::from_str(user_agent).ok())
+            .unwrap_or_else(|| HeaderValue::from_static(DEFAULT_USER_AGENT))
     }
 
-    fn setup_book_headers(&self) -> HeaderMap {
-        let mut headers = HeaderMap::new();
+    /// The `sec-ch-ua-platform` value a browser with this user-agent would send.
+    fn platform_hint(&self) -> &'static str {
+        let user_agent = self.user_agent.as_deref().unwrap_or(DEFAULT_USER_AGENT);
+        if user_agent.contains("Windows") {
+            "\"Windows\""
+        } else if user_agent.contains("Android") {
+            "\"Android\""
+        } else if user_agent.contains("iPhone") || user_agent.contains("iPad") {
+            "\"iOS\""
+        } else if user_agent.contains("CrOS") {
+            "\"Chrome OS\""
+        } else if user_agent.contains("Linux") {
+            "\"Linux\""
+        } else {
+            "\"macOS\""
+        }
+    }
 
-        // Content Type
-        headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/x-www-form-urlencoded"));
+    /// Posts a JSON payload to an arbitrary webhook, reusing the gateway's HTTP client.
+    pub async fn post_webhook(&self, url: &str, payload: &Value) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let res = self.client.post(url)
+            .json(payload)
+            .send()
+            .await
+            .map_err(send_error)?;
 
-        // Accept
-        headers.insert(ACCEPT, HeaderValue::from_static("application/json, text/plain, */*"));
+        if res.status().is_success() {
+            Ok(())
+        } else {
+            Err(Box::new(ResyAPIError::Request {
+                message: format!("Webhook request failed: {}", res.status()),
+                status: Some(res.status()),
+                retry_after: None,
+            }))
+        }
+    }
 
-        // Accept Language
-        headers.insert(ACCEPT_LANGUAGE, HeaderValue::from_static("en-US,en;q=0.9"));
+    /// Books reservation via the Resy API (dry run possible)
+    pub async fn book_reservation(&self, book_token: &str, payment_id: &str) -> APIResult {
+        let url = format!("{}/3/book", self.base_url);
+        let (headers, body) = match self.book_body_format {
+            BookBodyFormat::FormUrlencoded => {
+                let body = format!(
+                    "book_token={}&struct_payment_method={{\"id\":{}}}",
+                    urlencoding::encode(book_token), payment_id
+                );
+                (self.build_headers("application/x-www-form-urlencoded"), body)
+            }
+            BookBodyFormat::Json => {
+                // payment ids are numeric, but pass anything else through rather than guessing
+                let payment_method_id = payment_id.parse::<i64>().map_or_else(|_| json!(payment_id), |id| json!(id));
+                let body = json!({
+                    "book_token": book_token,
+                    "struct_payment_method": { "id": payment_method_id },
+                });
+                (self.build_headers("application/json"), body.to_string())
+            }
+        };
+
+        self.send_with_retry(|| {
+            self.client.post(&url)
+                .headers(headers.clone())
+                .body(body.clone())
+        }).await
+    }
+}
 
-        // Authorization and Token
-        headers.insert(AUTHORIZATION, HeaderValue::from_str(&format!("ResyAPI api_key=\"{}\"", self.api_key)).unwrap());
-        headers.insert("x-resy-auth-token", HeaderValue::from_str(&self.auth_token).unwrap());
-        headers.insert("x-resy-universal-auth", HeaderValue::from_str(&self.auth_token).unwrap());
+fn build_client(request_timeout: Duration, connect_timeout: Duration, proxy: Option<&Proxy>) -> reqwest::Result<Client> {
+    let mut builder = Client::builder()
+        .timeout(request_timeout)
+        .connect_timeout(connect_timeout);
+    if let Some(proxy) = proxy {
+        builder = builder.proxy(proxy.clone());
+    }
+    builder.build()
+}
 
-        // Additional headers from curl
-        headers.insert("cache-control", HeaderValue::from_static("no-cache"));
-        headers.insert("dnt", HeaderValue::from_static("1"));
-        headers.insert("origin", HeaderValue::from_static("https://widgets.resy.com"));
-        headers.insert("priority", HeaderValue::from_static("u=1, i"));
-        headers.insert("referer", HeaderValue::from_static("https://widgets.resy.com/"));
-        headers.insert("sec-ch-ua", HeaderValue::from_static("\"Not-A.Brand\";v=\"99\", \"Chromium\";v=\"124\""));
-        headers.insert("sec-ch-ua-mobile", HeaderValue::from_static("?0"));
-        headers.insert("sec-ch-ua-platform", HeaderValue::from_static("\"macOS\""));
-        headers.insert("sec-fetch-dest", HeaderValue::from_static("empty"));
-        headers.insert("sec-fetch-mode", HeaderValue::from_static("cors"));
-        headers.insert("sec-fetch-site", HeaderValue::from_static("same-site"));
-        headers.insert("user-agent", HeaderValue::from_static("Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/124.0.0.0 Safari/537.36"));
-        headers.insert("x-origin", HeaderValue::from_static("https://widgets.resy.com"));
+/// Reports timeouts as [`ResyAPIError::Timeout`] so callers can give up on the request quickly.
+fn send_error(error: reqwest::Error) -> Box<dyn Error + Send + Sync> {
+    if error.is_timeout() {
+        Box::new(ResyAPIError::Timeout)
+    } else {
+        Box::new(error)
+    }
+}
 
-        headers
+/// Reads a `Retry-After` value, either delay seconds or an HTTP date, clamped to
+/// `RETRY_AFTER_MAX_SECS`, with a date measured from `now`. Returns `None` if it can't be parsed.
+pub fn parse_retry_after(value: &str, now: DateTime<Utc>) -> Option<Duration> {
+    let value = value.trim();
+    let delay = match value.parse::<u64>() {
+        Ok(seconds) => Duration::from_secs(seconds),
+        Err(_) => {
+            let at = DateTime::parse_from_rfc2822(value).ok()?;
+            // a date already in the past means retry right away
+            (at.with_timezone(&Utc) - now).to_std().unwrap_or_default()
+        }
+    };
+    Some(delay.min(Duration::from_secs(RETRY_AFTER_MAX_SECS)))
+}
+
+/// Pulls the human readable reason out of an error response body, falling back to the raw text.
+fn error_reason(body: &str) -> Option<String> {
+    let body = body.trim();
+    if body.is_empty() {
+        return None;
     }
 
-    /// Books reservation via the Resy API (dry run possible)
-    pub async fn book_reservation(&self, book_token: &str, payment_id: &str) -> Result<Value, Box<dyn Error>> {
-        let url = format!("{}/3/book", RESY_API_BASE_URL);
-        let headers = self.setup_book_headers();
+    match serde_json::from_str::<Value>(body) {
+        Ok(json) => json.get("message")
+            .and_then(Value::as_str)
+            .map(str::to_string)
+            .or_else(|| Some(json.to_string())),
+        Err(_) => Some(body_snippet(body)),
+    }
+}
 
-        let body = format!(
-            "book_token={}&struct_payment_method={{\"id\":{}}}",
-            urlencoding::encode(book_token), payment_id
-        );
+const BODY_SNIPPET_CHARS: usize = 200;
 
-        let res = self.client.post(&url)
-            .headers(headers)
-            .body(body)
-            .send()
-            .await?;
+/// The start of a raw response body on one line, for error messages.
+fn body_snippet(body: &str) -> String {
+    let body = body.split_whitespace().collect::<Vec<_>>().join(" ");
+    if body.is_empty() {
+        return String::from("<empty body>");
+    }
 
-        Self::process_response(res).await
+    match body.char_indices().nth(BODY_SNIPPET_CHARS) {
+        Some((end, _)) => format!("{}...", &body[..end]),
+        None => body,
     }
-}
\ No newline at end of file
+}
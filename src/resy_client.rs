@@ -1,19 +1,32 @@
+use std::collections::{HashSet, VecDeque};
 use std::error::Error;
-use std::sync::Arc;
+use std::fs;
+use std::future::Future;
+use std::io::{self, IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::{Arc, Mutex as StdMutex};
+use std::sync::mpsc::Sender;
 use std::sync::atomic::{AtomicBool, Ordering};
-use std::thread;
 use std::time::{Duration as StdDuration, Instant};
-use futures::future::join_all;
-use chrono::{DateTime, Duration, Local, NaiveDate, NaiveTime, TimeZone, Utc};
-use log::{debug, error, info};
-use serde_json::{Value};
-use serde::Deserialize;
+use futures::future::{join_all, select_all};
+use chrono::{DateTime, Duration, Local, LocalResult, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Utc};
+use chrono_tz::Tz;
+use log::{debug, error, info, trace, warn};
+use serde_json::{json, Value};
+use serde::{Deserialize, Serialize};
 use tokio::sync::Mutex;
+use tokio::sync::mpsc::unbounded_channel;
 use rand;
 use rand::Rng;
 use tokio::time::{sleep, Duration as TokioDuration};
-use crate::config::Config;
-use crate::resy_api_gateway::ResyAPIGateway;
+use crate::clock::{Clock, SystemClock};
+use crate::config::{self, BookingRecord, Config, Credentials, FillPreference, VenueTarget};
+use crate::history::{self, HistoryEntry};
+use crate::stats;
+use crate::notifier;
+use crate::ntp;
+use crate::resy_api_gateway::{ResyAPIError, ResyAPIGateway};
 
 #[derive(Debug)]
 pub enum ResyClientError {
@@ -24,63 +37,374 @@ pub enum ResyClientError {
     InvalidInput(String),
     ParseError(String),
     BookingError(String),
+    /// every attempted slot failed, each with its own reason
+    AllSlotsFailed(Vec<(ResySlot, String)>),
+    AuthExpired,
+    Cancelled,
 }
 
 impl std::fmt::Display for ResyClientError {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        write!(f, "{:?}", self)
+        match self {
+            ResyClientError::AllSlotsFailed(failures) => {
+                write!(f, "AllSlotsFailed: {} slots failed", failures.len())?;
+                for (slot, reason) in failures {
+                    write!(f, "; {} {}: {}", slot.start, slot.slot_type, reason)?;
+                }
+                Ok(())
+            }
+            _ => write!(f, "{:?}", self),
+        }
     }
 }
 
 impl Error for ResyClientError {}
 
-type ResyResult<T> = Result<T, ResyClientError>;
+pub type ResyResult<T> = Result<T, ResyClientError>;
+
+// how long before the snipe fires to open a warm connection to the API
+const WARMUP_LEAD_SECS: i64 = 5;
+
+// pause between empty slot discovery polls at drop time
+const DISCOVERY_RETRY_DELAY_MS: u64 = 200;
+
+// pause between party sizes in a venue sweep
+const SWEEP_DELAY_MS: u64 = 500;
+
+// assumed book_token lifetime when the details response doesn't say; kept short to be safe
+const DEFAULT_BOOK_TOKEN_TTL_SECS: u64 = 60;
+
+// book tokens this close to expiring are treated as already expired
+const BOOK_TOKEN_EXPIRY_MARGIN_MS: u64 = 500;
+
+/// Per-run sniper switches that are not persisted to the config.
+#[derive(Debug, Default, Clone)]
+pub struct SnipeOptions {
+    /// render an in-place HH:MM:SS countdown instead of logging the remaining time
+    pub countdown: bool,
+    /// 1-based position in the ranked slot list to start attempts from (0 or 1 = closest first)
+    pub slot_rank: usize,
+    /// known slot config token to book directly, skipping slot discovery
+    pub token: Option<String>,
+    /// correct the countdown for local clock drift measured against `Config.ntp_server`
+    pub sync_clock: bool,
+    /// show a desktop notification with the result (also enabled by `Config.notifications_enabled`)
+    pub notify: bool,
+    /// webhook to post the result to, overriding `Config.webhook_url` for this run
+    pub webhook_url: Option<String>,
+    /// allow party sizes above `Config.max_allowed_party_size`
+    pub force_large: bool,
+    /// total discovery+book attempts before giving up (0 or 1 = a single attempt)
+    pub repeat: u32,
+    /// minutes to wait between repeated attempts
+    pub repeat_interval_mins: u64,
+    /// allow snipe times further out than `Config.max_snipe_horizon_days`
+    pub force: bool,
+    /// slots saved from `venue --json` to book instead of discovering them live
+    pub slots_file: Option<PathBuf>,
+    /// receives progress events, for embedders that want more than log lines
+    pub events: Option<Sender<SnipeEvent>>,
+    /// also snipe from every account in `Config.accounts` concurrently, keeping whichever books first
+    pub all_accounts: bool,
+    /// venue slugs to snipe in priority order, overriding `Config.venue_targets` for this run
+    pub venues: Vec<String>,
+    /// show the plan and fire time and ask for a "y" before waiting (skipped when stdin isn't a terminal)
+    pub confirm: bool,
+    /// greedy mode: skip ranking and the request stagger and book whichever slot's attempt gets
+    /// through first. Fastest to a booking, but it may be far from `Config.target_time` and the
+    /// unstaggered burst is likelier to be rate limited; the default launches the best ranked slots first
+    pub book_first: bool,
+}
+
+impl SnipeOptions {
+    fn emit(&self, event: SnipeEvent) {
+        if let Some(events) = &self.events {
+            // a dropped receiver just means nobody is listening anymore
+            let _ = events.send(event);
+        }
+    }
+}
+
+/// Progress reported by the sniper through [`SnipeOptions::events`].
+#[derive(Debug, Clone)]
+pub enum SnipeEvent {
+    /// the snipe time was validated and the sniper is waiting for it
+    WaitingUntil { at: DateTime<Utc> },
+    /// emitted on every countdown step while waiting
+    Tick { remaining: Duration },
+    SlotsFound { count: usize },
+    /// a booking attempt started for the slot starting at `time`
+    AttemptingSlot { time: String },
+    Booked { token: String },
+    Failed { reason: String },
+}
+
+/// Display order for listed slots.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SlotSort {
+    /// chronological by start
+    Time,
+    /// grouped by seating type, then chronological
+    Type,
+    /// smallest tables first
+    Size,
+}
+
+impl FromStr for SlotSort {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "time" => Ok(SlotSort::Time),
+            "type" => Ok(SlotSort::Type),
+            "size" => Ok(SlotSort::Size),
+            _ => Err(format!("invalid sort '{}', expected time, type or size", s)),
+        }
+    }
+}
+
+/// Settings for [`ResyClient::watch`], which polls for cancellations rather than sniping a known drop.
+#[derive(Debug, Clone)]
+pub struct WatchOptions {
+    /// pause between availability checks
+    pub interval: StdDuration,
+    /// give up after this long; `None` watches until interrupted
+    pub max_duration: Option<StdDuration>,
+    /// book the first matching slot instead of returning it
+    pub auto_book: bool,
+}
 
+/// What [`ResyClient::watch`] stopped on.
 #[derive(Debug)]
+pub enum WatchOutcome {
+    /// matching slots appeared and `auto_book` was off
+    Found(Vec<ResySlot>),
+    Booked(BookingOutcome),
+}
+
+/// What [`ResyClient::login`] got back from Resy.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LoginOutcome {
+    /// the new auth token is loaded
+    LoggedIn,
+    /// the account has 2FA; log in again with the one-time code Resy sent
+    OtpRequired,
+}
+
+/// Counters and timings collected during the last [`ResyClient::run_sniper`] call.
+#[derive(Debug, Clone, Default)]
+pub struct SnipeMetrics {
+    /// slots returned by discovery (or loaded from a slots file), summed over repeats
+    pub slots_discovered: usize,
+    /// reservation detail requests made, one per slot tried
+    pub booking_attempts: usize,
+    /// from firing at snipe time to the first book token, if one was acquired
+    pub time_to_book_token: Option<StdDuration>,
+    /// from firing at snipe time (or from the start, if it never fired) to the result
+    pub total_elapsed: StdDuration,
+    fired_at: Option<Instant>,
+}
+
+impl SnipeMetrics {
+    /// Whether the snipe time was reached, i.e. the other numbers describe an actual attempt.
+    pub fn fired(&self) -> bool {
+        self.fired_at.is_some()
+    }
+}
+
+impl std::fmt::Display for SnipeMetrics {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Snipe summary: {} slots discovered, {} booking attempts, ", self.slots_discovered, self.booking_attempts)?;
+        match self.time_to_book_token {
+            Some(elapsed) => write!(f, "first book token after {}ms, ", elapsed.as_millis())?,
+            None => write!(f, "no book token, ")?,
+        }
+        write!(f, "{}ms total", self.total_elapsed.as_millis())
+    }
+}
+
+/// A confirmed reservation returned by the sniper.
+#[derive(Debug, Clone)]
+pub struct BookingOutcome {
+    pub resy_token: String,
+    /// slot start, or the reservation date when a token was booked directly
+    pub start: String,
+    /// empty when a token was booked directly
+    pub slot_type: String,
+    pub party_size: u8,
+    /// the venue that was booked, which differs from `Config.venue_slug` in a multi-venue snipe
+    pub venue_slug: String,
+    /// the reservation date, which can differ from `Config.date` when `Config.acceptable_dates` is set
+    pub date: String,
+}
+
+impl std::fmt::Display for BookingOutcome {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.resy_token)
+    }
+}
+
+/// High level Resy client that owns the [`Config`] it operates on.
+///
+/// To embed it, build a client from a loaded config with [`ResyClient::from_config`] (see
+/// [`crate::config::read_config`]), then call [`ResyClient::get_payment_id`] once so bookings
+/// have a card to charge, [`ResyClient::view_venue`] to resolve a venue and list its slots, and
+/// finally [`ResyClient::run_sniper`] to wait for the drop and book. Every call updates
+/// `config` in place, so persist it afterwards with [`crate::config::write_config`] to keep
+/// the state the CLI would.
+#[derive(Debug, Clone)]
 pub struct ResyClient {
     pub config: Config,
     api_gateway: ResyAPIGateway,
+    // shared by the clones handed to booking tasks so they all count into the same run
+    metrics: Arc<StdMutex<SnipeMetrics>>,
+    verbose_slots: bool,
+    clock: Arc<dyn Clock>,
+    venue_info: Option<VenueInfo>,
+    // where history and stats are recorded, unset without a home directory
+    data_dir: Option<PathBuf>,
+}
+
+impl Default for ResyClient {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl ResyClient {
-    pub(crate) fn new() -> Self {
+    pub fn new() -> Self {
         ResyClient {
             config: Config::default(),
             api_gateway: ResyAPIGateway::new(),
+            metrics: Arc::default(),
+            verbose_slots: false,
+            clock: Arc::new(SystemClock),
+            venue_info: None,
+            data_dir: config::default_data_dir(),
         }
     }
 
-    pub(crate) fn from_config(config: Config) -> Self {
+    /// Fails only if the configured proxy url is malformed.
+    pub fn from_config(mut config: Config) -> ResyResult<Self> {
+        config.resolve_active_profile();
+
         let api_key = config.api_key.clone();
         let auth_token = config.auth_token.clone();
+        let max_retries = config.max_retries;
 
-        ResyClient {
-            config,
-            api_gateway: ResyAPIGateway::from_auth(api_key, auth_token),
+        let mut api_gateway = ResyAPIGateway::from_auth(api_key, auth_token)
+            .with_max_retries(max_retries)
+            .with_timeouts(
+                StdDuration::from_millis(config.request_timeout_ms),
+                StdDuration::from_millis(config.connect_timeout_ms),
+            );
+        api_gateway.set_base_url(config.api_base_url.as_deref());
+        api_gateway.set_user_agent(config.user_agent.clone());
+        api_gateway.set_book_body_format(config.book_body_format);
+
+        if let Some(proxy_url) = &config.proxy_url {
+            api_gateway = api_gateway.with_proxy(proxy_url)
+                .map_err(|e| ResyClientError::InvalidInput(e.to_string()))?;
         }
-    }
 
-    pub(crate) fn load_config(&mut self, config: Config) {
-        let api_key = config.api_key.clone();
-        let auth_token = config.auth_token.clone();
+        Ok(ResyClient {
+            config,
+            api_gateway,
+            metrics: Arc::default(),
+            verbose_slots: false,
+            clock: Arc::new(SystemClock),
+            venue_info: None,
+            data_dir: config::default_data_dir(),
+        })
+    }
 
+    /// Replaces the config; the existing gateway (and its connection pool) is reused with the new credentials,
+    /// so a changed `proxy_url` only takes effect through [`ResyClient::from_config`].
+    pub fn load_config(&mut self, config: Config) {
+        self.api_gateway.set_auth(config.api_key.clone(), config.auth_token.clone());
+        self.api_gateway.set_max_retries(config.max_retries);
+        self.api_gateway.set_base_url(config.api_base_url.as_deref());
+        self.api_gateway.set_user_agent(config.user_agent.clone());
+        self.api_gateway.set_book_body_format(config.book_body_format);
         self.config = config;
-        self.api_gateway = ResyAPIGateway::from_auth(api_key, auth_token);
     }
 
-    pub(crate) fn update_auth(&mut self, api_key: String, auth_token: String) {
-        let api_key_clone = api_key.clone();
-        let auth_token_clone = auth_token.clone();
+    /// Replaces the clock used for the snipe countdown, the "in the past" check, the pause between
+    /// discovery polls and recorded timestamps (the system clock by default).
+    pub fn set_clock(&mut self, clock: Arc<dyn Clock>) {
+        self.api_gateway.set_clock(Arc::clone(&clock));
+        self.clock = clock;
+    }
+
+    /// Records history and stats in `data_dir` instead of `~/.marksman`, e.g. the one
+    /// [`config::get_data_dir`] gives for a config loaded from elsewhere.
+    pub fn set_data_dir(&mut self, data_dir: PathBuf) {
+        self.data_dir = Some(data_dir);
+    }
+
+    pub fn clock(&self) -> &dyn Clock {
+        self.clock.as_ref()
+    }
+
+    /// The gateway every request from this client goes through.
+    pub fn api_gateway(&self) -> &ResyAPIGateway {
+        &self.api_gateway
+    }
+
+    /// Logs raw vs parsed slot counts and every raw slot that could not be parsed, to spot Resy response changes.
+    pub fn set_verbose_slots(&mut self, verbose_slots: bool) {
+        self.verbose_slots = verbose_slots;
+    }
+
+    /// Updates credentials without rebuilding the gateway, so warmed connections are kept.
+    pub fn update_auth(&mut self, api_key: String, auth_token: String) {
+        self.api_gateway.set_auth(api_key.clone(), auth_token.clone());
 
         self.config.api_key = api_key;
         self.config.auth_token = auth_token;
+    }
+
+    /// Logs in with email and password and loads the returned auth token, along with the
+    /// account's payment id when Resy includes it. Needs `Config.api_key` to be set.
+    pub async fn login(&mut self, email: &str, password: &str, otp: Option<&str>) -> ResyResult<LoginOutcome> {
+        if self.config.api_key.is_empty() {
+            return Err(ResyClientError::InvalidInput("api_key is not set; pass --api-key or run `marksman load` first".to_string()));
+        }
 
-        self.api_gateway = ResyAPIGateway::from_auth(api_key_clone, auth_token_clone)
+        let json = match self.api_gateway.login(email, password, otp).await {
+            Ok(json) => json,
+            Err(e) => {
+                return Err(match e.downcast_ref::<ResyAPIError>() {
+                    Some(ResyAPIError::AuthExpired(_)) => ResyClientError::InvalidInput("Resy rejected the email, password or code".to_string()),
+                    _ => map_api_error(e.as_ref(), || ResyClientError::ApiError(format!("Error logging in: {}", e))),
+                });
+            }
+        };
+
+        match json["token"].as_str() {
+            Some(token) => {
+                self.update_auth(self.config.api_key.clone(), token.to_string());
+                if let Some(payment_id) = json["payment_method_id"].as_i64() {
+                    self.config.payment_id = payment_id.to_string();
+                }
+                Ok(LoginOutcome::LoggedIn)
+            }
+            // 2FA accounts get an answer without a token until the one-time code is sent too
+            None if otp.is_none() => {
+                debug!("Login response without a token: {}", json);
+                Ok(LoginOutcome::OtpRequired)
+            }
+            None => Err(ResyClientError::ParseError(format!("Login response has no token: {}", json))),
+        }
     }
 
-    pub(crate) async fn view_venue(&mut self, url: Option<&str>, date: Option<&str>, party_size: Option<u8>, target_time: Option<&str>) -> ResyResult<(String, Vec<ResySlot>)> {
+    pub async fn view_venue(&mut self, url: Option<&str>, date: Option<&str>, party_size: Option<u8>, target_time: Option<&str>, force_large: bool) -> ResyResult<(String, Vec<ResySlot>)> {
+        if let Some(party_size) = party_size {
+            self._check_party_size(party_size, force_large)?;
+        }
+
         if let Some(url) = url {
-            let _ = self.load_venue_id_from_url(url).await?;
+            self.venue_info = Some(self.load_venue_id_from_url(url).await?);
         }
 
         if let Some(date) = date {
@@ -89,122 +413,924 @@ impl ResyClient {
             self.config.date = parsed_date.to_string();
         }
 
-        if let Some(party_size) = party_size {
-            self.config.party_size = party_size;
+        if let Some(party_size) = party_size {
+            self.config.party_size = party_size;
+        }
+
+        if let Some(target_time) = target_time {
+            parse_hhmm(target_time)?;
+            self.config.target_time = Some(target_time.to_string());
+        } else {
+            self.config.target_time = None;
+        }
+
+        let slots = self._find_reservation_slots(self.config.party_size).await?;
+        let mut slots = dedup_slots(self._rank_slots(slots, self.config.party_size));
+
+        // closeness to target_time already orders the slots, otherwise list them chronologically
+        if self.config.target_time.is_none() {
+            sort_slots(&mut slots, SlotSort::Time);
+        }
+
+        let venue_id = self.config.venue_id.clone();
+        Ok((venue_id, slots))
+    }
+
+    /// Lists the ranked slots for each party size from 1 to `max_party_size` at the configured venue and date.
+    pub async fn sweep_party_sizes(&self, max_party_size: u8) -> ResyResult<Vec<(u8, Vec<ResySlot>)>> {
+        let mut sweeps = Vec::new();
+
+        for party_size in 1..=max_party_size {
+            // space out the requests (including from the venue lookup) so a sweep doesn't look like a burst
+            sleep(TokioDuration::from_millis(SWEEP_DELAY_MS)).await;
+
+            let slots = self._find_reservation_slots(party_size).await?;
+            sweeps.push((party_size, self._rank_slots(slots, party_size)));
+        }
+
+        Ok(sweeps)
+    }
+
+    pub async fn run_sniper(&mut self, snipe_time: &str, snipe_date: &str, options: &SnipeOptions) -> ResyResult<BookingOutcome> {
+        let started = Instant::now();
+        self.metrics = Arc::default();
+
+        let result = if options.all_accounts && !self.config.accounts.is_empty() {
+            self._snipe_all_accounts(snipe_time, snipe_date, options).await
+        } else {
+            self._snipe(snipe_time, snipe_date, options).await
+        };
+
+        // with several acceptable dates, the booked one becomes the date on record
+        if let Ok(outcome) = &result {
+            self.config.date = outcome.date.clone();
+        }
+
+        self._record_metrics(|metrics| metrics.total_elapsed = metrics.fired_at.unwrap_or(started).elapsed());
+        let metrics = self.last_snipe_metrics();
+        if metrics.fired() {
+            info!("{}", metrics);
+        }
+
+        // the user stopped it themselves, so there's nothing to report
+        if let Err(ResyClientError::Cancelled) = result {
+            return result;
+        }
+
+        match &result {
+            Ok(outcome) => {
+                info!("Snipe succeeded: booked {} @ {} for party of {} (resy_token: {})", outcome.venue_slug, outcome.start, outcome.party_size, outcome);
+                options.emit(SnipeEvent::Booked { token: outcome.resy_token.clone() });
+            }
+            Err(e) => {
+                error!("Snipe failed for {} on {}: {}", self.config.venue_slug, self.config.date, e);
+                options.emit(SnipeEvent::Failed { reason: e.to_string() });
+            }
+        }
+
+        if options.notify || self.config.notifications_enabled {
+            match &result {
+                Ok(outcome) => notifier::notify("Reservation booked!", &format!("{} @ {}", outcome.venue_slug, outcome.start)),
+                Err(e) => notifier::notify("Snipe failed", &format!("{}: {}", self.config.venue_slug, e)),
+            }
+        }
+
+        let webhook_url = options.webhook_url.as_ref().or(self.config.webhook_url.as_ref());
+        if let Some(webhook_url) = webhook_url {
+            self._post_result_webhook(webhook_url, &result).await;
+        }
+
+        self._record_history(&result);
+        if metrics.fired() {
+            self._record_stats(&result, &metrics);
+        }
+
+        let outcome = result?;
+        self._remember_booking(&outcome);
+        Ok(outcome)
+    }
+
+    /// What is known about the venue most recently loaded by [`ResyClient::view_venue`]. A venue
+    /// that was already loaded isn't fetched again, so only its id, name and neighborhood are set.
+    pub fn venue_info(&self) -> Option<&VenueInfo> {
+        self.venue_info.as_ref()
+    }
+
+    /// Metrics from the most recent [`ResyClient::run_sniper`] call.
+    pub fn last_snipe_metrics(&self) -> SnipeMetrics {
+        self.metrics.lock().map(|metrics| metrics.clone()).unwrap_or_default()
+    }
+
+    fn _record_metrics<F: FnOnce(&mut SnipeMetrics)>(&self, update: F) {
+        if let Ok(mut metrics) = self.metrics.lock() {
+            update(&mut metrics);
+        }
+    }
+
+    /// Whether `snipe_timeout_secs` have passed since the snipe fired (never, before it fires or with a timeout of 0).
+    fn _snipe_deadline_passed(&self) -> bool {
+        let timeout = StdDuration::from_secs(self.config.snipe_timeout_secs);
+        self.metrics.lock().ok()
+            .and_then(|metrics| metrics.fired_at)
+            .is_some_and(|fired_at| timeout > StdDuration::ZERO && fired_at.elapsed() >= timeout)
+    }
+
+    fn _remember_booking(&mut self, outcome: &BookingOutcome) {
+        self.config.last_booking = Some(BookingRecord {
+            resy_token: outcome.resy_token.clone(),
+            venue_slug: outcome.venue_slug.clone(),
+            date: self.config.date.clone(),
+            party_size: outcome.party_size,
+            booked_at: self.clock.now_utc().to_rfc3339(),
+        });
+    }
+
+    /// Appends the result to the bookings history; failures to write are logged and ignored.
+    fn _record_history(&self, result: &ResyResult<BookingOutcome>) {
+        let entry = HistoryEntry {
+            recorded_at: self.clock.now_utc().to_rfc3339(),
+            venue_slug: result.as_ref().map_or(self.config.venue_slug.clone(), |outcome| outcome.venue_slug.clone()),
+            date: self.config.date.clone(),
+            time: result.as_ref().ok().map(|outcome| outcome.start.clone()),
+            party_size: result.as_ref().map_or(self.config.party_size, |outcome| outcome.party_size),
+            success: result.is_ok(),
+            resy_token: result.as_ref().ok().map(|outcome| outcome.resy_token.clone()),
+            reason: result.as_ref().err().map(|e| e.to_string()),
+        };
+
+        let Some(data_dir) = &self.data_dir else {
+            warn!("Failed to record booking history: no data directory");
+            return;
+        };
+        if let Err(e) = history::append(data_dir, &entry) {
+            warn!("Failed to record booking history: {:#}", e);
+        }
+    }
+
+    /// Adds a fired snipe to the per-venue stats; failures to write are logged and ignored.
+    fn _record_stats(&self, result: &ResyResult<BookingOutcome>, metrics: &SnipeMetrics) {
+        let Some(data_dir) = &self.data_dir else {
+            warn!("Failed to record snipe stats: no data directory");
+            return;
+        };
+        let venue_slug = result.as_ref().map_or(self.config.venue_slug.as_str(), |outcome| outcome.venue_slug.as_str());
+        if let Err(e) = stats::record(data_dir, venue_slug, self.config.fire_offset_ms, result.is_ok(), metrics.time_to_book_token) {
+            warn!("Failed to record snipe stats: {:#}", e);
+        }
+    }
+
+    /// Delivery failures are logged and never affect the booking result.
+    async fn _post_result_webhook(&self, url: &str, result: &ResyResult<BookingOutcome>) {
+        let (success, time, token, content) = match result {
+            Ok(outcome) => (true, Some(outcome.start.as_str()), Some(outcome.resy_token.as_str()), format!("Booked {} @ {}", outcome.venue_slug, outcome.start)),
+            Err(e) => (false, None, None, format!("Snipe for {} on {} failed: {}", self.config.venue_slug, self.config.date, e)),
+        };
+        let venue_slug = result.as_ref().map_or(self.config.venue_slug.as_str(), |outcome| outcome.venue_slug.as_str());
+
+        // `content` lets chat webhooks (e.g. Discord) render a readable message
+        let payload = json!({
+            "venue_slug": venue_slug,
+            "date": self.config.date,
+            "time": time,
+            "success": success,
+            "token": token,
+            "content": content,
+        });
+
+        if let Err(e) = self.api_gateway.post_webhook(url, &payload).await {
+            warn!("Failed to deliver webhook to {}: {}", url, e);
+        }
+    }
+
+    /// Waits for the snipe time and books, returning the confirmed reservation.
+    async fn _snipe(&mut self, snipe_time: &str, snipe_date: &str, options: &SnipeOptions) -> ResyResult<BookingOutcome> {
+        // only booking charges a card, so dry runs and slot lookups work without one
+        if self.config.payment_id.is_empty() {
+            return Err(ResyClientError::InvalidInput("payment_id is not set; run `marksman load --select` to pick a card".to_string()));
+        }
+
+        if options.token.is_some() && self.config.date.is_empty() {
+            return Err(ResyClientError::InvalidInput("date must be set to book a token directly".to_string()));
+        }
+
+        self._check_snipe_party_sizes(options)?;
+
+        // load the snapshot up front so a bad file fails now rather than at snipe time
+        let snapshot = match &options.slots_file {
+            Some(path) => Some(load_slots_file(path)?),
+            None => None,
+        };
+
+        // venue ids are looked up now so the lookups don't cost time after the drop
+        let venues = self._resolve_venue_targets(options).await?;
+
+        // a malformed stored setting fails now rather than hours later at the drop
+        if options.token.is_none() {
+            if venues.is_empty() {
+                self._check_config(true)?;
+            }
+            for venue in &venues {
+                venue._check_config(true)?;
+            }
+        }
+
+        self._await_snipe_time(snipe_time, snipe_date, options).await?;
+        // with several accounts the first one to fire starts the clock
+        self._record_metrics(|metrics| {
+            metrics.fired_at.get_or_insert_with(Instant::now);
+        });
+
+        if let Some(slots) = snapshot {
+            return self._snipe_from_snapshot(slots, &venues, options).await;
+        }
+
+        match &options.token {
+            // skip slot discovery entirely when the slot token is already known
+            Some(config_id) => {
+                let book_mutex = Arc::new(Mutex::new(()));
+                let booking_successful = Arc::new(AtomicBool::new(false));
+                self._sniper_task(config_id, &self.config.date, "", book_mutex, booking_successful).await
+            }
+            None => self._snipe_with_repeats(&venues, options).await,
+        }
+    }
+
+    /// One client per venue of a multi-venue snipe, in priority order; empty for a single-venue snipe.
+    async fn _resolve_venue_targets(&self, options: &SnipeOptions) -> ResyResult<Vec<ResyClient>> {
+        // a known slot token already pins the venue
+        if options.token.is_some() {
+            return Ok(Vec::new());
+        }
+
+        let targets: Vec<VenueTarget> = if options.venues.is_empty() {
+            self.config.venue_targets.clone()
+        } else {
+            options.venues.iter().map(|slug| {
+                // reuse any id we already know for this slug
+                let venue_id = self.config.venue_targets.iter()
+                    .find(|target| &target.venue_slug == slug)
+                    .map(|target| target.venue_id.clone())
+                    .or_else(|| (slug == &self.config.venue_slug).then(|| self.config.venue_id.clone()))
+                    .unwrap_or_default();
+                VenueTarget { venue_slug: slug.clone(), venue_id }
+            }).collect()
+        };
+
+        let mut venues = Vec::with_capacity(targets.len());
+        for target in targets {
+            let venue_id = if target.venue_id.is_empty() {
+                let venue_info = self.api_gateway.get_venue(&target.venue_slug, &self.config.location).await
+                    .map_err(|e| map_api_error(e.as_ref(), || ResyClientError::ApiError(format!("Error fetching venue {}: {:?}", target.venue_slug, e))))?;
+                venue_info["id"]["resy"].as_u64()
+                    .ok_or_else(|| ResyClientError::NotFound(format!("Venue ID not found for {}", target.venue_slug)))?
+                    .to_string()
+            } else {
+                target.venue_id
+            };
+
+            let mut client = self.clone();
+            client.config.venue_slug = target.venue_slug;
+            client.config.venue_id = venue_id;
+            venues.push(client);
+        }
+
+        if !venues.is_empty() {
+            let slugs: Vec<&str> = venues.iter().map(|venue| venue.config.venue_slug.as_str()).collect();
+            info!("Sniping {} venues in priority order: {}", venues.len(), slugs.join(", "));
+        }
+        Ok(venues)
+    }
+
+    /// Discovers slots at every venue at once, then books venue by venue in priority order, so a
+    /// higher priority venue never waits on discovery at a lower priority one.
+    async fn _snipe_venues(&self, venues: &[ResyClient], options: &SnipeOptions) -> ResyResult<BookingOutcome> {
+        let discoveries: Vec<_> = venues.iter().map(|venue| {
+            let venue = venue.clone();
+            let options = options.clone();
+            tokio::spawn(async move { venue._discover_slots(&options).await })
+        }).collect();
+
+        let mut last_error = None;
+        let mut result = None;
+        for (venue, discovery) in venues.iter().zip(discoveries) {
+            if result.is_some() {
+                discovery.abort();
+                continue;
+            }
+
+            let booking = match discovery.await {
+                Ok(Ok((party_size, slots))) => venue._for_party_size(party_size)._book_first_available(slots, options).await,
+                Ok(Err(e)) => Err(e),
+                Err(e) => Err(ResyClientError::InternalError(format!("Discovery task failed: {}", e))),
+            };
+
+            match booking {
+                Ok(outcome) => result = Some(Ok(outcome)),
+                Err(ResyClientError::AuthExpired) => result = Some(Err(ResyClientError::AuthExpired)),
+                Err(e) => {
+                    warn!("Could not book {}: {}", venue.config.venue_slug, e);
+                    last_error = Some(e);
+                }
+            }
+        }
+
+        result.unwrap_or_else(|| Err(last_error.unwrap_or_else(|| ResyClientError::NotFound("no venues to snipe".to_string()))))
+    }
+
+    /// Runs the same snipe from the main account and every configured extra account at once.
+    /// The first booking wins and the other snipes are dropped, though a booking request already
+    /// in flight on another account can still land.
+    async fn _snipe_all_accounts(&mut self, snipe_time: &str, snipe_date: &str, options: &SnipeOptions) -> ResyResult<BookingOutcome> {
+        let mut accounts = Vec::new();
+        for (index, credentials) in self.config.accounts.iter().enumerate() {
+            let name = if credentials.name.is_empty() { format!("account {}", index + 1) } else { credentials.name.clone() };
+            let mut client = self._for_account(credentials);
+            if client.config.payment_id.is_empty() {
+                client.get_payment_id().await
+                    .map_err(|e| ResyClientError::InvalidInput(format!("Could not fetch payment_id for {}: {}", name, e)))?;
+            }
+            accounts.push((name, client));
+        }
+        info!("Sniping from {} accounts", accounts.len() + 1);
+
+        // only the main account drives the countdown and progress events
+        let secondary_options = SnipeOptions { countdown: false, events: None, confirm: false, ..options.clone() };
+        let clients = std::iter::once(("main", self, options))
+            .chain(accounts.iter_mut().map(|(name, client)| (name.as_str(), client, &secondary_options)));
+        let snipes = clients.map(|(name, client, options)| async move {
+            client._snipe(snipe_time, snipe_date, options).await
+                .map(|outcome| (name, outcome))
+                .map_err(|e| {
+                    warn!("Snipe from {} failed: {}", name, e);
+                    e
+                })
+        });
+
+        // declining the main account's confirmation must stop the other accounts too
+        let (name, outcome) = first_ok_unless_cancelled(snipes).await?;
+        info!("Booked from {}", name);
+        Ok(outcome)
+    }
+
+    /// A copy of this client that authenticates and pays as `credentials`.
+    fn _for_account(&self, credentials: &Credentials) -> ResyClient {
+        let mut client = self.clone();
+        client.config.api_key = credentials.api_key.clone();
+        client.config.auth_token = credentials.auth_token.clone();
+        client.config.payment_id = credentials.payment_id.clone();
+        client.api_gateway.set_auth(credentials.api_key.clone(), credentials.auth_token.clone());
+        client
+    }
+
+    /// Books from previously saved slots, falling back to live discovery if none of them can be booked.
+    async fn _snipe_from_snapshot(&self, slots: Vec<ResySlot>, venues: &[ResyClient], options: &SnipeOptions) -> ResyResult<BookingOutcome> {
+        warn!("Booking from {} saved slots; their tokens may be stale", slots.len());
+        self._record_metrics(|metrics| metrics.slots_discovered += slots.len());
+        let slots = if options.book_first { slots } else { self._rank_slots(slots, self.config.party_size) };
+
+        match self._book_first_available(slots, options).await {
+            Err(e @ (ResyClientError::BookingError(_) | ResyClientError::AllSlotsFailed(_))) => {
+                warn!("No saved slot could be booked ({}), falling back to live discovery", e);
+                self._snipe_with_repeats(venues, options).await
+            }
+            result => result,
+        }
+    }
+
+    /// Runs discovery and booking, re-running both after a failure until `options.repeat` attempts are used.
+    async fn _snipe_with_repeats(&self, venues: &[ResyClient], options: &SnipeOptions) -> ResyResult<BookingOutcome> {
+        let attempts = options.repeat.max(1);
+        let mut attempt = 1;
+
+        loop {
+            if attempts > 1 {
+                info!("Snipe attempt {}/{}", attempt, attempts);
+            }
+
+            let result = if !venues.is_empty() {
+                self._snipe_venues(venues, options).await
+            } else {
+                match self._discover_slots(options).await {
+                    Ok((party_size, slots)) => self._for_party_size(party_size)._book_first_available(slots, options).await,
+                    Err(e) => Err(e),
+                }
+            };
+
+            match result {
+                Err(ResyClientError::AuthExpired) => return result, // every later attempt would fail the same way
+                Err(e) if attempt < attempts => {
+                    warn!("Snipe attempt {} failed: {}; retrying in {} minutes", attempt, e, options.repeat_interval_mins);
+                    tokio::select! {
+                        _ = sleep(TokioDuration::from_secs(options.repeat_interval_mins * 60)) => {}
+                        _ = tokio::signal::ctrl_c() => return Err(ResyClientError::Cancelled),
+                    }
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    /// Snipes `dining_date` at the moment it is released: `days_before` days earlier at `drop_time` (HHMM).
+    pub async fn run_scheduled_sniper(&mut self, dining_date: &str, days_before: u32, drop_time: &str, options: &SnipeOptions) -> ResyResult<BookingOutcome> {
+        let date = NaiveDate::parse_from_str(dining_date, "%Y-%m-%d")
+            .map_err(|_| ResyClientError::InvalidInput("Invalid date format. Please use YYYY-MM-DD.".to_string()))?;
+        let snipe_date = date - Duration::days(days_before as i64);
+
+        // the snipe itself rejects drop times that have already passed
+        self.config.date = date.to_string();
+        info!("Reservations for {} drop on {} at {}", date, snipe_date, drop_time);
+
+        self.run_sniper(drop_time, &snipe_date.to_string(), options).await
+    }
+
+    /// Runs the full timing and slot discovery path, but only requests uncommitted reservation
+    /// details and never books. Returns the slot that would have been booked.
+    pub async fn run_dry_sniper(&mut self, snipe_time: &str, snipe_date: &str, options: &SnipeOptions) -> ResyResult<ResySlot> {
+        self._check_snipe_party_sizes(options)?;
+        self._check_config(false)?;
+        self._await_snipe_time(snipe_time, snipe_date, options).await?;
+        let (party_size, slots) = self._discover_slots(options).await?;
+        let client = self._for_party_size(party_size);
+
+        for slot in slots {
+            let client = client._for_slot_date(&slot).unwrap_or_else(|| client.clone());
+            match client._dry_run_task(&slot.token, &slot.start).await {
+                Ok(()) => {
+                    info!("DRY RUN - would have booked {} (token: {})", slot.start, slot.token);
+                    return Ok(slot)
+                }
+                Err(e) => debug!("Dry run skipped {}: {}", slot.start, e),
+            }
+        }
+
+        Err(ResyClientError::BookingError("Dry run failure: all slots failed".to_string()))
+    }
+
+    /// Polls the configured venue and date until slots matching the seating and time filters
+    /// appear, for venues that release cancellations rather than a fixed drop. Ctrl-C returns
+    /// `Cancelled`; running past `max_duration` returns `NotFound`.
+    pub async fn watch(&mut self, options: &WatchOptions) -> ResyResult<WatchOutcome> {
+        if self.config.venue_id.is_empty() {
+            return Err(ResyClientError::InvalidInput("No venue loaded; run `venue -u <url>` first".to_string()));
+        }
+        // a watch can run for hours, so don't find out about a missing card only once a slot opens
+        if options.auto_book {
+            self._check_config(true)?;
+        }
+
+        let started = Instant::now();
+        let mut checks = 0u64;
+
+        loop {
+            checks += 1;
+            match self._find_reservation_slots(self.config.party_size).await {
+                Ok(slots) if !slots.is_empty() => {
+                    info!("Found {} matching slots after {} checks", slots.len(), checks);
+                    let slots = self._rank_slots(slots, self.config.party_size);
+                    if !options.auto_book {
+                        return Ok(WatchOutcome::Found(slots));
+                    }
+
+                    let result = self._book_first_available(slots, &SnipeOptions::default()).await;
+                    self._record_history(&result);
+                    match result {
+                        Ok(outcome) => {
+                            self._remember_booking(&outcome);
+                            return Ok(WatchOutcome::Booked(outcome));
+                        }
+                        Err(ResyClientError::AuthExpired) => return Err(ResyClientError::AuthExpired),
+                        // someone else got there first, so keep watching for the next opening
+                        Err(e) => warn!("Found slots but could not book them: {}", e),
+                    }
+                }
+                Ok(_) => debug!("No matching slots on check {}", checks),
+                Err(ResyClientError::AuthExpired) => return Err(ResyClientError::AuthExpired),
+                Err(e) => warn!("Availability check {} failed: {}", checks, e),
+            }
+
+            if let Some(max_duration) = options.max_duration {
+                if started.elapsed() + options.interval > max_duration {
+                    return Err(ResyClientError::NotFound(format!("No availability after watching for {} minutes", started.elapsed().as_secs() / 60)));
+                }
+            }
+
+            tokio::select! {
+                _ = sleep(options.interval) => {}
+                _ = tokio::signal::ctrl_c() => return Err(ResyClientError::Cancelled),
+            }
+        }
+    }
+
+    /// Waits for the snipe time, returning `Cancelled` if interrupted with Ctrl-C so the caller can still save state.
+    async fn _await_snipe_time(&mut self, snipe_time: &str, snipe_date: &str, options: &SnipeOptions) -> ResyResult<()> {
+        tokio::select! {
+            result = self._wait_for_snipe_time(snipe_time, snipe_date, options) => result,
+            _ = tokio::signal::ctrl_c() => {
+                if options.countdown {
+                    println!();
+                }
+                warn!("Snipe cancelled while waiting for snipe time");
+                Err(ResyClientError::Cancelled)
+            }
+        }
+    }
+
+    /// Prints what is about to be booked and when, returning `Cancelled` unless the user answers "y".
+    async fn _confirm_plan(&self, fire_at: DateTime<Utc>, options: &SnipeOptions) -> ResyResult<()> {
+        let venue = self.config.venue_display_name().unwrap_or_else(|| self.config.venue_slug.clone());
+        let venues = if options.venues.is_empty() {
+            self.config.venue_targets.iter().map(|target| target.venue_slug.clone()).collect()
+        } else {
+            options.venues.clone()
+        };
+        // the card is only for display, so a failed lookup just falls back to the id
+        let card = match self.get_payment_methods().await {
+            Ok(methods) => methods.into_iter()
+                .find(|method| method.id == self.config.payment_id)
+                .map(|method| format!("{} ending in {}", method.method_type, method.last4)),
+            Err(e) => {
+                debug!("Could not look up payment methods for the confirmation: {}", e);
+                None
+            }
+        };
+
+        println!("Snipe plan:");
+        if venues.is_empty() {
+            println!("  venue:       {}", venue);
+        } else {
+            println!("  venues:      {}", venues.join(", "));
+        }
+        println!("  date:        {}", self.config.date);
+        println!("  party size:  {}", self.config.party_size);
+        println!("  target time: {}", self.config.target_time.as_deref().unwrap_or("any"));
+        println!("  payment:     {}", card.unwrap_or_else(|| format!("id {}", self.config.payment_id)));
+        println!("  fires at:    {}", fire_at.with_timezone(&Local).format("%Y-%m-%d %H:%M:%S%.3f %Z"));
+        print!(">> Proceed? [y/N]: ");
+        let _ = io::stdout().flush();
+
+        // read on a blocking thread so Ctrl-C is still noticed while the prompt is open
+        let answer = tokio::task::spawn_blocking(|| {
+            let mut input = String::new();
+            io::stdin().read_line(&mut input).map(|_| input)
+        }).await
+            .map_err(|e| ResyClientError::InternalError(e.to_string()))?
+            .map_err(|e| ResyClientError::InternalError(format!("Failed to read confirmation: {}", e)))?;
+
+        if answer.trim().eq_ignore_ascii_case("y") {
+            Ok(())
+        } else {
+            warn!("Snipe not confirmed");
+            Err(ResyClientError::Cancelled)
+        }
+    }
+
+    /// Validates the snipe date/time (falling back to the stored values) and waits until it arrives.
+    async fn _wait_for_snipe_time(&mut self, snipe_time: &str, snipe_date: &str, options: &SnipeOptions) -> ResyResult<()> {
+        // Check if snipe_date is provided and valid, else use the stored config value
+        let date = if !snipe_date.is_empty() {
+            NaiveDate::parse_from_str(snipe_date, "%Y-%m-%d")
+                .map_err(|_| ResyClientError::InvalidInput("Invalid date format".to_string()))?
+        } else {
+            NaiveDate::parse_from_str(&self.config.snipe_date, "%Y-%m-%d")
+                .map_err(|_| ResyClientError::InvalidInput("Stored date format is invalid".to_string()))?
+        };
+
+        // Check if snipe_time is provided and valid, else use the stored config value
+        let time = if !snipe_time.is_empty() {
+            NaiveTime::parse_from_str(snipe_time, "%H%M")
+                .map_err(|_| ResyClientError::InvalidInput("Invalid time format".to_string()))?
+        } else {
+            NaiveTime::parse_from_str(&self.config.snipe_time, "%H%M")
+                .map_err(|_| ResyClientError::InvalidInput("Stored time format is invalid".to_string()))?
+        };
+
+        // drops open at the venue's local time, which may differ from this machine's
+        let naive_datetime = date.and_time(time);
+        let datetime = match &self.config.venue_timezone {
+            Some(timezone) => {
+                let timezone: Tz = timezone.parse()
+                    .map_err(|_| ResyClientError::InvalidInput(format!("Unknown venue timezone '{}'", timezone)))?;
+                resolve_local_datetime(&timezone, naive_datetime)
+                    .ok_or(ResyClientError::InvalidInput("Could not convert to venue datetime".to_string()))?
+                    .with_timezone(&Utc)
+            }
+            None => resolve_local_datetime(&Local, naive_datetime)
+                .ok_or(ResyClientError::InvalidInput("Could not convert to local datetime".to_string()))?
+                .with_timezone(&Utc),
+        };
+
+        let clock_offset = if options.sync_clock {
+            self._measure_clock_offset().await
+        } else {
+            Duration::zero()
+        };
+        let clock = self.clock.clone();
+        let now = move || clock.now_utc() + clock_offset;
+
+        if datetime <= now() + Duration::minutes(1) {
+            return Err(ResyClientError::InvalidInput("Snipe date/time is in the past".to_string()));
+        }
+
+        // a snipe months out is almost always a typo'd year
+        let horizon = now() + Duration::days(self.config.max_snipe_horizon_days as i64);
+        if datetime > horizon && !options.force {
+            return Err(ResyClientError::InvalidInput(format!(
+                "Snipe date/time {} is more than {} days out (latest allowed {}); pass --force if this is intended",
+                datetime.with_timezone(&Local).format("%Y-%m-%d %H:%M"),
+                self.config.max_snipe_horizon_days,
+                horizon.with_timezone(&Local).format("%Y-%m-%d %H:%M"),
+            )));
+        }
+
+        self.config.snipe_date = if !snipe_date.is_empty() { snipe_date.to_string() } else { self.config.snipe_date.clone() };
+        self.config.snipe_time = if !snipe_time.is_empty() { snipe_time.to_string() } else { self.config.snipe_time.clone() };
+
+        // negative offsets fire early to cover a Resy clock running ahead of ours, positive ones fire late
+        let datetime = datetime + Duration::milliseconds(self.config.fire_offset_ms);
+        if self.config.fire_offset_ms != 0 {
+            info!("Firing {}ms {} the snipe time", self.config.fire_offset_ms.abs(), if self.config.fire_offset_ms < 0 { "before" } else { "after" });
+        }
+
+        if options.confirm && io::stdin().is_terminal() {
+            self._confirm_plan(datetime, options).await?;
+        }
+
+        options.emit(SnipeEvent::WaitingUntil { at: datetime });
+        let mut remaining = datetime - now();
+
+        // fall back to logging when the countdown can't be redrawn in place
+        let countdown = options.countdown && io::stdout().is_terminal();
+
+        let seconds_to_sleep = remaining.num_seconds() % 60;
+        if seconds_to_sleep > 0 && !countdown {
+            self.clock.sleep(StdDuration::from_secs(seconds_to_sleep as u64)).await;
+        }
+
+        remaining = datetime - now();
+        let mut warmed_up = false;
+        while remaining > Duration::seconds(0) {
+            options.emit(SnipeEvent::Tick { remaining });
+
+            if !warmed_up && remaining <= Duration::seconds(WARMUP_LEAD_SECS) {
+                self._warm_up_connection();
+                warmed_up = true;
+            }
+
+            let mut step = next_sleep_duration(remaining);
+            if countdown {
+                print!("\rTime remaining: {}", format_countdown(remaining));
+                let _ = io::stdout().flush();
+                // redraw at least every second
+                step = step.min(Duration::seconds(1));
+            } else if remaining > Duration::minutes(2) {
+                info!("Time remaining: {} minutes", remaining.num_minutes());
+            } else if step >= Duration::seconds(1) {
+                // skip the sub-second steps so the final approach doesn't flood the log
+                info!("Time remaining: {} seconds", remaining.num_seconds());
+            }
+
+            self.clock.sleep(step.to_std().unwrap_or_default()).await;
+            remaining = datetime - now();
+        }
+
+        if countdown {
+            println!();
+        }
+
+        Ok(())
+    }
+
+    /// Fires a cheap request in the background so the connection pool holds a live
+    /// keep-alive connection (DNS + TLS already done) when the snipe fires.
+    fn _warm_up_connection(&self) {
+        let api_gateway = self.api_gateway.clone();
+        tokio::spawn(async move {
+            match api_gateway.get_user().await {
+                Ok(_) => debug!("Connection warmed up"),
+                Err(e) => warn!("Connection warmup failed (ignored): {:?}", e),
+            }
+        });
+    }
+
+    /// Best effort: returns a zero offset if the NTP server can't be reached.
+    async fn _measure_clock_offset(&self) -> Duration {
+        match ntp::query_offset(&self.config.ntp_server).await {
+            Ok(offset) => {
+                info!("Local clock offset from {}: {}ms", self.config.ntp_server, offset.num_milliseconds());
+                offset
+            }
+            Err(e) => {
+                warn!("Clock sync with {} failed, using local time: {}", self.config.ntp_server, e);
+                Duration::zero()
+            }
+        }
+    }
+
+    /// Returns the party size that has availability (the configured one first, then each
+    /// fallback size in order) and its ranked slots, rotated to start at the requested slot rank.
+    /// Polling stops once `snipe_timeout_secs` have passed since the snipe fired.
+    async fn _discover_slots(&self, options: &SnipeOptions) -> ResyResult<(u8, Vec<ResySlot>)> {
+        if !self.config.validate() {
+            return Err(ResyClientError::InvalidInput("reservation config is not complete".to_string()));
         }
 
-        if let Some(target_time) = target_time {
-            if target_time.len() == 4 && target_time.chars().all(|c| c.is_digit(10)) {
-                let hours = &target_time[..2].parse::<u32>().unwrap();
-                let minutes = &target_time[2..].parse::<u32>().unwrap();
-                if *hours < 24 && *minutes < 60 {
-                    self.config.target_time = Some(target_time.to_string());
-                } else {
-                    return Err(ResyClientError::InvalidInput("Invalid time format. Please use HHMM format, where HH is 00 to 23 and MM is 00 to 59.".to_string()));
+        let mut party_sizes = vec![self.config.party_size];
+        party_sizes.extend(self.config.fallback_party_sizes.iter().filter(|&&size| size != self.config.party_size));
+
+        // inventory often lags the drop by a second or two, so keep polling briefly before giving up
+        let mut attempt = 0;
+        let (party_size, slots) = 'poll: loop {
+            for &party_size in &party_sizes {
+                let slots = self._find_slots_on_acceptable_dates(party_size).await?;
+                if !slots.is_empty() {
+                    break 'poll (party_size, slots);
                 }
-            } else {
-                return Err(ResyClientError::InvalidInput("Invalid time format. Please use HHMM format, where HH is 00 to 23 and MM is 00 to 59.".to_string()));
+                debug!("No slots found for party of {}", party_size);
             }
-        } else {
-            self.config.target_time = None;
-        }
+            if attempt >= self.config.discovery_retries {
+                return Err(ResyClientError::NotFound("no reservation slots available".to_string()));
+            }
+            if self._snipe_deadline_passed() {
+                warn!("No slots found within {}s of firing, giving up", self.config.snipe_timeout_secs);
+                return Err(ResyClientError::NotFound("deadline exceeded".to_string()));
+            }
+            attempt += 1;
+            debug!("No slots found yet, retrying discovery ({}/{})", attempt, self.config.discovery_retries);
+            self.clock.sleep(StdDuration::from_millis(DISCOVERY_RETRY_DELAY_MS)).await;
+        };
+        options.emit(SnipeEvent::SlotsFound { count: slots.len() });
+        self._record_metrics(|metrics| metrics.slots_discovered += slots.len());
+        let mut slots = if options.book_first { slots } else { self._rank_slots(slots, party_size) };
 
-        let mut slots = self._find_reservation_slots().await?;
-        if let Some(target_time) = &self.config.target_time {
-            slots = sort_slots_by_closest_time(slots, target_time);
+        if options.slot_rank > 1 {
+            let offset = (options.slot_rank - 1) % slots.len();
+            slots.rotate_left(offset);
         }
 
-        let venue_id = self.config.venue_id.clone();
-        Ok((venue_id, slots))
+        Ok((party_size, slots))
     }
 
-    pub(crate) async fn run_sniper(&mut self, snipe_time: &str, snipe_date: &str) -> ResyResult<String> {
-        // Check if snipe_date is provided and valid, else use the stored config value
-        let date = if !snipe_date.is_empty() {
-            NaiveDate::parse_from_str(snipe_date, "%Y-%m-%d")
-                .map_err(|_| ResyClientError::InvalidInput("Invalid date format".to_string()))?
+    /// Fails with every problem [`Config::validation_errors`] finds, plus a missing payment method when `booking`.
+    fn _check_config(&self, booking: bool) -> ResyResult<()> {
+        let errors = if booking { self.config.booking_validation_errors() } else { self.config.validation_errors() };
+        if errors.is_empty() {
+            Ok(())
         } else {
-            NaiveDate::parse_from_str(&self.config.snipe_date, "%Y-%m-%d")
-                .map_err(|_| ResyClientError::InvalidInput("Stored date format is invalid".to_string()))?
-        };
+            Err(ResyClientError::InvalidInput(format!("reservation config is not complete: {}", errors.join("; "))))
+        }
+    }
 
-        // Check if snipe_time is provided and valid, else use the stored config value
-        let time = if !snipe_time.is_empty() {
-            NaiveTime::parse_from_str(snipe_time, "%H%M")
-                .map_err(|_| ResyClientError::InvalidInput("Invalid time format".to_string()))?
-        } else {
-            NaiveTime::parse_from_str(&self.config.snipe_time, "%H%M")
-                .map_err(|_| ResyClientError::InvalidInput("Stored time format is invalid".to_string()))?
-        };
+    /// Guards against typos like `-p 12` booking a huge table.
+    fn _check_party_size(&self, party_size: u8, force_large: bool) -> ResyResult<()> {
+        if party_size > self.config.max_allowed_party_size && !force_large {
+            return Err(ResyClientError::InvalidInput(format!(
+                "party size {} is above the allowed maximum of {}; pass --force-large to book it anyway",
+                party_size, self.config.max_allowed_party_size
+            )));
+        }
+        Ok(())
+    }
 
-        let naive_datetime = date.and_time(time);
-        let datetime = Local.from_local_datetime(&naive_datetime).single()
-            .ok_or(ResyClientError::InvalidInput("Could not convert to local datetime".to_string()))?;
+    fn _check_snipe_party_sizes(&self, options: &SnipeOptions) -> ResyResult<()> {
+        self._check_party_size(self.config.party_size, options.force_large)?;
+        for &party_size in &self.config.fallback_party_sizes {
+            self._check_party_size(party_size, options.force_large)?;
+        }
+        Ok(())
+    }
 
-        if datetime <= Local::now() + Duration::minutes(1) {
-            return Err(ResyClientError::InvalidInput("Snipe date/time is in the past".to_string()));
+    /// Returns a client that books at `party_size`, leaving the configured size untouched.
+    fn _for_party_size(&self, party_size: u8) -> ResyClient {
+        let mut client = self.clone();
+        if party_size != self.config.party_size {
+            warn!("No slots for party of {}, booking fallback party of {}", self.config.party_size, party_size);
+            client.config.party_size = party_size;
         }
+        client
+    }
 
-        self.config.snipe_date = if !snipe_date.is_empty() { snipe_date.to_string() } else { self.config.snipe_date.clone() };
-        self.config.snipe_time = if !snipe_time.is_empty() { snipe_time.to_string() } else { self.config.snipe_time.clone() };
+    /// Attempts the slots in rank order, `Config.max_concurrent_attempts` at a time; the first to
+    /// confirm wins and the rest are aborted.
+    async fn _book_first_available(&self, slots: Vec<ResySlot>, options: &SnipeOptions) -> ResyResult<BookingOutcome> {
+        let client = Arc::new(self.clone());
+        let book_mutex = Arc::new(Mutex::new(()));
+        let booking_successful = Arc::new(AtomicBool::new(false));
 
-        let mut remaining = datetime - Local::now();
+        let max_jitter_ms = if options.book_first { 0 } else { self.config.request_jitter_ms };
 
-        let seconds_to_sleep = remaining.num_seconds() % 60;
-        if seconds_to_sleep > 0 {
-            sleep(TokioDuration::from_secs(seconds_to_sleep as u64)).await;
+        // one attempt per slot at once can mean dozens of simultaneous requests, which trips Resy's rate limiting
+        let max_concurrent = self.config.max_concurrent_attempts.max(1);
+        if slots.len() > max_concurrent {
+            info!("Attempting {} slots, {} at a time", slots.len(), max_concurrent);
         }
 
-        remaining = datetime - Local::now();
-        while remaining > Duration::seconds(0) {
-            if remaining <= Duration::minutes(2) {
-                // Log more frequently as the time approaches
-                info!("Time remaining: {} seconds", remaining.num_seconds());
-                sleep(TokioDuration::from_secs(1)).await;
+        for slot in &slots {
+            if slot.quantity <= 1 {
+                warn!("Slot {} ({}) has {} table(s) left, expect a race for it", slot.start, slot.slot_type, slot.quantity);
             } else {
-                // Log periodically
-                info!("Time remaining: {} minutes", remaining.num_minutes());
-                sleep(TokioDuration::from_secs(60)).await;
+                info!("Slot {} ({}) has {} tables left", slot.start, slot.slot_type, slot.quantity);
             }
-            remaining = datetime - Local::now();
         }
 
+        // each worker takes the best ranked slot left, so a lower ranked slot never gets ahead of a better one
+        let worker_count = max_concurrent.min(slots.len());
+        let queue = Arc::new(StdMutex::new(VecDeque::from(slots)));
+        let (results, mut finished) = unbounded_channel();
 
-        if !self.config.validate() {
-            return Err(ResyClientError::InvalidInput("reservation config is not complete".to_string()));
+        let workers: Vec<_> = (0..worker_count).map(|_| {
+            let client = Arc::clone(&client);
+            let queue = Arc::clone(&queue);
+            let results = results.clone();
+            let book_mutex = Arc::clone(&book_mutex);
+            let booking_successful = Arc::clone(&booking_successful);
+            let options = options.clone();
+
+            tokio::spawn(async move {
+                while let Some(slot) = next_slot(&queue) {
+                    let client = client._for_slot_date(&slot).map_or_else(|| Arc::clone(&client), Arc::new);
+                    // stagger the burst slightly so our own requests don't trip Resy's rate limiting
+                    let jitter_ms = rand::thread_rng().gen_range(0..=max_jitter_ms);
+                    trace!("Delaying snipe @ {} by {}ms", slot.start, jitter_ms);
+                    sleep(TokioDuration::from_millis(jitter_ms)).await;
+
+                    options.emit(SnipeEvent::AttemptingSlot { time: slot.start.clone() });
+                    let result = client._sniper_task(&slot.token, &slot.start, &slot.slot_type, Arc::clone(&book_mutex), Arc::clone(&booking_successful)).await;
+                    if results.send((slot, result)).is_err() {
+                        break; // nobody is waiting for results anymore
+                    }
+                }
+            })
+        }).collect();
+        // the channel closes once every worker has run out of slots
+        drop(results);
+
+        // every reason is kept so a lost race (all 410s) can be told apart from e.g. a payment problem
+        let mut failures = Vec::new();
+        while let Some((slot, result)) = finished.recv().await {
+            match result {
+                Ok(booked) => {
+                    for worker in &workers {
+                        worker.abort();
+                    }
+                    return Ok(booked)
+                }
+                Err(ResyClientError::AuthExpired) => {
+                    // every other attempt will fail the same way
+                    for worker in &workers {
+                        worker.abort();
+                    }
+                    return Err(ResyClientError::AuthExpired)
+                }
+                Err(e) => {
+                    debug!("Snipe task for {} failed: {}", slot.start, e);
+                    failures.push((slot, e.to_string()));
+                }
+            }
         }
 
-        let mut slots = self._find_reservation_slots().await?;
-        if let Some(target_time) = &self.config.target_time {
-            slots = sort_slots_by_closest_time(slots, target_time);
+        for worker in workers {
+            if let Err(e) = worker.await {
+                error!("Snipe task panicked {:?}", e);
+            }
         }
 
-        if slots.is_empty() {
-            return Err(ResyClientError::NotFound("no reservation slots available".to_string()));
+        if failures.is_empty() {
+            return Err(ResyClientError::BookingError("Booking failure: all slots failed".to_string()));
         }
+        Err(ResyClientError::AllSlotsFailed(failures))
+    }
 
-        for slot in slots {
-            match self._sniper_task(&slot.token, &slot.start).await {
-                Ok(tok) => {
-                    return Ok(tok)
-                }
-                Err(e) => {}
+    async fn _dry_run_task(&self, config_id: &str, time_slot: &str) -> ResyResult<()> {
+        info!("Running dry run snipe @ {} (token: {})", time_slot, config_id);
+
+        match self.api_gateway.get_reservation_details(0, config_id, self.config.party_size, &self.config.date, self.config.source_id.as_deref()).await {
+            Ok(json) => {
+                debug!("Reservation details response {:#?}", json);
+                Ok(())
+            }
+            Err(e) => {
+                error!("Error getting reservation details {:?}", e);
+                Err(map_api_error(e.as_ref(), || ResyClientError::BookingError("Error fetching reservation details".to_string())))
             }
         }
-
-        Err(ResyClientError::BookingError("Booking failure: all slots failed".to_string()))
     }
 
-    async fn _sniper_task(&self, config_id: &str, time_slot: &str) -> ResyResult<String> {
+    async fn _sniper_task(&self, config_id: &str, time_slot: &str, slot_type: &str, book_mutex: Arc<Mutex<()>>, booking_successful: Arc<AtomicBool>) -> ResyResult<BookingOutcome> {
+        if booking_successful.load(Ordering::SeqCst) {
+            return Err(ResyClientError::BookingError("Already got a booking".to_string()));
+        }
+
         info!("Running snipe @ {} (token: {})", time_slot, config_id);
+        self._record_metrics(|metrics| metrics.booking_attempts += 1);
 
-        let book_token = match self.api_gateway.get_reservation_details(1, &config_id, self.config.party_size, &self.config.date).await {
+        let (book_token, token_ttl) = match self.api_gateway.get_reservation_details(1, &config_id, self.config.party_size, &self.config.date, self.config.source_id.as_deref()).await {
             Ok(json) => {
                 debug!("Reservation details response {:#?}", json);
 
                 if json.get("book_token").is_some() {
                     match json["book_token"]["value"].as_str() {
-                        Some(token) => token.to_string(),
+                        Some(token) => (token.to_string(), book_token_ttl(&json["book_token"], self.clock.now_utc())),
                         None => return Err(ResyClientError::BookingError("Book token not found".to_string()))
                     }
                 } else {
@@ -213,11 +1339,31 @@ impl ResyClient {
             }
             Err(e) => {
                 error!("Error getting book token {:?}", e);
-                return Err(ResyClientError::BookingError("Error fetching book token".to_string()))
+                return Err(map_api_error(e.as_ref(), || ResyClientError::BookingError("Error fetching book token".to_string())))
             }
         };
 
-        info!("Book token acquired @ {} (token: {})", time_slot, book_token);
+        let token_acquired = Instant::now();
+        info!("Book token acquired @ {} (token: {}, valid for {}s)", time_slot, book_token, token_ttl.as_secs());
+        self._record_metrics(|metrics| {
+            if metrics.time_to_book_token.is_none() {
+                metrics.time_to_book_token = metrics.fired_at.map(|fired_at| fired_at.elapsed());
+            }
+        });
+
+        // locked block, one booking at a time
+        let _guard = book_mutex.lock().await;
+
+        if booking_successful.load(Ordering::SeqCst) {
+            info!("Already got a booking!");
+            return Err(ResyClientError::BookingError("Already got a booking".to_string())); // recheck after acquiring the lock
+        }
+
+        // waiting on the lock behind other slots can outlast the token, and a dead token wastes a book call
+        if token_acquired.elapsed() + StdDuration::from_millis(BOOK_TOKEN_EXPIRY_MARGIN_MS) >= token_ttl {
+            warn!("Book token for {} expired before book", time_slot);
+            return Err(ResyClientError::BookingError(format!("Book token for {} expired before book", time_slot)));
+        }
 
         return match self.api_gateway.book_reservation(&book_token, &self.config.payment_id).await {
             Ok(json) => {
@@ -225,227 +1371,514 @@ impl ResyClient {
 
                 match json.get("resy_token") {
                     Some(token) => {
-                        info!("acquired {} (token: {})", time_slot, token);
-                        Ok(token.to_string())
+                        booking_successful.store(true, Ordering::SeqCst);
+                        // the token is a JSON string; fall back to its raw form if the shape ever changes
+                        let resy_token = token.as_str().map(str::to_string).unwrap_or_else(|| token.to_string());
+                        info!("acquired {} (token: {})", time_slot, resy_token);
+                        Ok(BookingOutcome {
+                            resy_token,
+                            start: time_slot.to_string(),
+                            slot_type: slot_type.to_string(),
+                            party_size: self.config.party_size,
+                            venue_slug: self.config.venue_slug.clone(),
+                            date: self.config.date.clone(),
+                        })
                     },
-                    None => Err(ResyClientError::BookingError("Error booking reservation".to_string())),
+                    None => Err(ResyClientError::BookingError(format!("Error booking reservation: no resy_token in response {}", json))),
                 }
             }
             Err(e) => {
                 error!("Error booking reservation {:?}", e);
-                Err(ResyClientError::BookingError("Error booking reservation".to_string()))
+                Err(map_api_error(e.as_ref(), || ResyClientError::BookingError(format!("Error booking reservation: {}", e))))
             }
         };
     }
 
-    // pub(crate) async fn run_snipe(self: Arc<ResyClient>) -> ResyResult<String> {
-    //     if !self.config.validate() {
-    //         return Err(ResyClientError::InvalidInput("reservation config is not complete".to_string()));
-    //     }
-    //
-    //     let mut slots = self._find_reservation_slots().await?;
-    //
-    //     if slots.is_empty() {
-    //         return Err(ResyClientError::NotFound("no reservation slots available".to_string()));
-    //     }
-    //
-    //     let mut tasks = vec![];
-    //     let mutex = Arc::new(Mutex::new(()));
-    //     let booking_successful = Arc::new(AtomicBool::new(false));
-    //
-    //     for slot in slots {
-    //         // Only spawn tasks if the slot has a valid 'config_id'
-    //         let cloned_config_id = slot.token.clone();
-    //         let time_slot = slot.start.clone();
-    //         let self_clone: Arc<ResyClient> = Arc::clone(&self);
-    //         let lock = mutex.clone();
-    //         let booking_successful_clone = Arc::clone(&booking_successful);
-    //
-    //         tasks.push(tokio::spawn(async move {
-    //             self_clone._snipe_task(cloned_config_id, time_slot, lock, booking_successful_clone).await
-    //         }));
-    //     }
-    //
-    //     let results = join_all(tasks).await;
-    //
-    //     Ok("Placeholder for compilation".to_string())
-    // }
-    //
-    // async fn _snipe_task(&self, config_id: String, time_slot: String, book_mutex: Arc<Mutex<()>>, booking_successful: Arc<AtomicBool>) -> Option<String> {
-    //     info!("Running snipe @ {} (token: {})", time_slot, config_id);
-    //
-    //     let book_token = match self.api_gateway.get_reservation_details(1, &config_id, self.config.party_size, &self.config.date).await {
-    //         Ok(json) => {
-    //             debug!("Reservation details response {:#?}", json);
-    //
-    //             if json.get("book_token").is_some() {
-    //                 match json["book_token"]["value"].as_str() {
-    //                     Some(token) => token.to_string(),
-    //                     None => return None,
-    //                 }
-    //             } else {
-    //                 return None // didn't get it in time!
-    //             }
-    //         }
-    //         Err(e) => {
-    //             error!("Error getting book token {:?}", e);
-    //             return None
-    //         }
-    //     };
-    //
-    //     info!("Book token acquired @ {} (token: {})", time_slot, book_token);
-    //
-    //     // locked block, one task at a time
-    //     {
-    //         let _guard = book_mutex.lock().await;
-    //
-    //         if booking_successful.load(Ordering::SeqCst) {
-    //             info!("Already got a booking!");
-    //             return None; // Recheck the flag after acquiring the lock to avoid race condition
-    //         }
-    //
-    //         // let mut rng = rand::thread_rng(); // Get a random number generator
-    //         // let num = rng.gen_range(0..=1);
-    //         //
-    //         // if num != 0 {
-    //         //     println!("locked a reservation");
-    //         //     booking_successful.store(true, Ordering::SeqCst);
-    //         //     return true
-    //         // }
-    //         // println!("failed a reservation");
-    //
-    //         let resy_token = match self.api_gateway.book_reservation(&book_token, &self.config.payment_id).await {
-    //             Ok(json) => {
-    //                 debug!("Booking reservation response {:#?}", json);
-    //
-    //                 match json.get("resy_token") {
-    //                     Some(token) => {
-    //                         booking_successful.store(true, Ordering::SeqCst);
-    //                         info!("acquired {} (token: {})", time_slot, token);
-    //                         Some(token.to_string())
-    //                     },
-    //                     None => None,
-    //                 }
-    //             }
-    //             Err(e) => {
-    //                 error!("Error booking reservation {:?}", e);
-    //                 None
-    //             }
-    //         };
-    //
-    //         info!("token... @ {:?}", resy_token);
-    //     }
-    //
-    //     None
-    // }
-
-    pub(crate) async fn get_payment_id(&mut self) -> ResyResult<String> {
+    /// Looks up the card to book with and stores its id. A `Config.payment_preference` is
+    /// resolved again on every call, so the same card is picked even if its id changes.
+    pub async fn get_payment_id(&mut self) -> ResyResult<String> {
         match self.api_gateway.get_user().await {
             Ok(user_data) => {
+                if let Some(preference) = &self.config.payment_preference {
+                    let payment_methods = format_payment_methods(&user_data)?;
+                    let payment_method = match choose_payment_method(&payment_methods, preference) {
+                        Some(payment_method) => payment_method,
+                        None => {
+                            warn!("No payment method matches '{}', using the first one on the account", preference);
+                            &payment_methods[0]
+                        }
+                    };
+                    self.config.payment_id = payment_method.id.clone();
+                    return Ok(payment_method.id.clone());
+                }
+
                 // First try to get the payment_method_id directly from the root
                 if let Some(payment_id) = user_data["payment_method_id"].as_i64() {
                     self.config.payment_id = payment_id.to_string();
                     return Ok(payment_id.to_string());
                 }
 
-                // If not found, try the payment_methods array
-                let payment_methods = user_data["payment_methods"]
-                    .as_array()
-                    .ok_or_else(|| ResyClientError::NotFound("No payment method found in resy account".to_string()))?;
+                // If not found, try the payment_methods array, preferring the default card
+                let payment_methods = format_payment_methods(&user_data)?;
+                let payment_method = payment_methods.iter()
+                    .find(|method| method.is_default)
+                    .or_else(|| payment_methods.first())
+                    .ok_or_else(|| ResyClientError::NotFound("Payment method list is empty".to_string()))?;
 
-                let payment_id = payment_methods.get(0)
-                    .ok_or_else(|| ResyClientError::NotFound("Payment method list is empty".to_string()))?
-                    .get("id")
-                    .and_then(|id| id.as_i64())
-                    .map(|id| id.to_string())
-                    .ok_or_else(|| ResyClientError::NotFound("Payment ID not found".to_string()))?;
+                self.config.payment_id = payment_method.id.clone();
+                Ok(payment_method.id.clone())
+            }
+            Err(e) => {
+                Err(map_api_error(e.as_ref(), || ResyClientError::ApiError(format!("Error fetching payment_id: {:?}", e))))
+            }
+        }
+    }
 
-                self.config.payment_id = payment_id.clone();
-                Ok(payment_id)
+    /// Lists every payment method on the account, for choosing which one to book with.
+    pub async fn get_payment_methods(&self) -> ResyResult<Vec<PaymentMethod>> {
+        match self.api_gateway.get_user().await {
+            Ok(user_data) => format_payment_methods(&user_data),
+            Err(e) => {
+                Err(map_api_error(e.as_ref(), || ResyClientError::ApiError(format!("Error fetching payment methods: {:?}", e))))
             }
+        }
+    }
+
+    /// Remembers which card to book with ("default", a card type like "amex", or an id) and
+    /// resolves it to an id now; the previous preference is kept if the lookup fails.
+    pub async fn set_payment_preference(&mut self, preference: &str) -> ResyResult<String> {
+        let previous = self.config.payment_preference.replace(preference.to_string());
+        let result = self.get_payment_id().await;
+        if result.is_err() {
+            self.config.payment_preference = previous;
+        }
+        result
+    }
+
+    /// Makes `payment_id` the card to book with, after checking it belongs to the account.
+    /// This replaces any `Config.payment_preference`.
+    pub async fn set_payment_id(&mut self, payment_id: &str) -> ResyResult<PaymentMethod> {
+        let methods = self.get_payment_methods().await?;
+        let ids: Vec<&str> = methods.iter().map(|method| method.id.as_str()).collect();
+        let known_ids = ids.join(", ");
+
+        let method = methods.into_iter()
+            .find(|method| method.id == payment_id)
+            .ok_or_else(|| ResyClientError::NotFound(format!("No payment method {} on this account (available: {})", payment_id, known_ids)))?;
+
+        self.config.payment_id = method.id.clone();
+        self.config.payment_preference = None;
+        Ok(method)
+    }
+
+    pub async fn search_venues(&self, query: &str) -> ResyResult<Vec<VenueMatch>> {
+        match self.api_gateway.search_venues(query, &self.config.location).await {
+            Ok(json) => Ok(format_venue_matches(json)),
             Err(e) => {
-                Err(ResyClientError::ApiError(format!("Error fetching payment_id: {:?}", e)))
+                Err(map_api_error(e.as_ref(), || ResyClientError::ApiError(format!("Error searching venues: {:?}", e))))
             }
         }
     }
 
-    async fn load_venue_id_from_url(&mut self, url: &str) -> ResyResult<u64> {
+    /// Updates one setting by its `marksman set` key; `venue-url` also looks up the venue id.
+    pub async fn set_config_value(&mut self, key: &str, value: &str) -> ResyResult<()> {
+        if key == "venue-url" {
+            return self.load_venue_id_from_url(value).await.map(|_| ());
+        }
+
+        self.config.set(key, value).map_err(|e| ResyClientError::InvalidInput(e.to_string()))
+    }
+
+    /// Makes a search result the target venue.
+    pub fn use_venue(&mut self, venue: &VenueMatch) {
+        self.config.venue_slug = venue.slug.clone();
+        self.config.venue_id = venue.venue_id.clone();
+        self.config.venue_name = Some(venue.name.clone());
+        self.config.venue_neighborhood = Some(venue.neighborhood.clone()).filter(|neighborhood| !neighborhood.is_empty());
+    }
+
+    async fn load_venue_id_from_url(&mut self, url: &str) -> ResyResult<VenueInfo> {
         let venue_slug = extract_venue_slug(url)?;
+
+        // venue ids are stable per slug, so skip the lookup when this venue is already loaded
+        if venue_slug == self.config.venue_slug {
+            if let Ok(venue_id) = self.config.venue_id.parse::<u64>() {
+                debug!("Using cached venue id {} for {}", venue_id, venue_slug);
+                return Ok(VenueInfo {
+                    id: venue_id,
+                    slug: venue_slug,
+                    name: self.config.venue_name.clone(),
+                    neighborhood: self.config.venue_neighborhood.clone(),
+                    ..VenueInfo::default()
+                });
+            }
+        }
+
         self.config.venue_slug = venue_slug.clone();
+        self.config.venue_name = None;
+        self.config.venue_neighborhood = None;
 
-        match self.api_gateway.get_venue(venue_slug.as_str()).await {
-            Ok(venue_info) => {
-                if let Some(venue_id) = venue_info["id"]["resy"].as_u64() {
-                    self.config.venue_id = venue_id.to_string();
+        match self.api_gateway.get_venue(venue_slug.as_str(), &self.config.location).await {
+            Ok(venue_json) => {
+                if let Some(venue_info) = parse_venue_info(&venue_slug, &venue_json) {
+                    self.config.venue_id = venue_info.id.to_string();
+                    self.config.venue_name = venue_info.name.clone();
+                    self.config.venue_neighborhood = venue_info.neighborhood.clone();
 
-                    Ok(venue_id)
+                    Ok(venue_info)
                 } else {
                     Err(ResyClientError::NotFound("Venue ID not found".to_string()))
                 }
             }
             Err(e) => {
-                Err(ResyClientError::ApiError(format!("Error fetching venue: {:?}", e)))
+                Err(map_api_error(e.as_ref(), || ResyClientError::ApiError(format!("Error fetching venue: {:?}", e))))
             }
         }
     }
 
-    async fn _find_reservation_slots(&self) -> ResyResult<Vec<ResySlot>> {
-        match self.api_gateway.find_reservation(self.config.venue_id.as_str(), self.config.date.as_str(), self.config.party_size, self.config.target_time.as_deref()).await {
-            Ok(json) => Ok(format_slots(json)),
+    /// Slots on every date in `Config.acceptable_dates`, queried concurrently and in date order,
+    /// or just on `Config.date` when none are set.
+    async fn _find_slots_on_acceptable_dates(&self, party_size: u8) -> ResyResult<Vec<ResySlot>> {
+        if self.config.acceptable_dates.is_empty() {
+            return self._find_reservation_slots(party_size).await;
+        }
+
+        let clients: Vec<ResyClient> = self.config.acceptable_dates.iter().map(|date| {
+            let mut client = self.clone();
+            client.config.date = date.clone();
+            client
+        }).collect();
+        let results = join_all(clients.iter().map(|client| client._find_reservation_slots(party_size))).await;
+
+        let mut slots = Vec::new();
+        for (client, result) in clients.iter().zip(results) {
+            let found = result?;
+            debug!("Found {} slots on {}", found.len(), client.config.date);
+            slots.extend(found);
+        }
+        Ok(slots)
+    }
+
+    /// A copy of this client that books on the slot's own date, when that is another of `Config.acceptable_dates`.
+    fn _for_slot_date(&self, slot: &ResySlot) -> Option<ResyClient> {
+        let date = slot.start.get(..10)?;
+        if date == self.config.date || !self.config.acceptable_dates.iter().any(|acceptable| acceptable == date) {
+            return None;
+        }
+        let mut client = self.clone();
+        client.config.date = date.to_string();
+        Some(client)
+    }
+
+    async fn _find_reservation_slots(&self, party_size: u8) -> ResyResult<Vec<ResySlot>> {
+        // without the server-side filter every slot comes back and _rank_slots still orders by target_time
+        let time_filter = self.config.target_time.as_deref().filter(|_| self.config.server_side_time_filter);
+
+        match self.api_gateway.find_reservation(self.config.venue_id.as_str(), self.config.date.as_str(), party_size, time_filter).await {
+            Ok(json) => {
+                let slots = filter_slots_by_party_size(format_slots(json, self.verbose_slots), party_size);
+                let slots = filter_slots_by_window(slots, self.config.earliest_time.as_deref(), self.config.latest_time.as_deref());
+                Ok(match &self.config.seating_preference {
+                    Some(seating) => filter_slots_by_seating(slots, seating),
+                    None => slots,
+                })
+            }
             Err(e) => {
-                Err(ResyClientError::ApiError(format!("Error fetching venue: {:?}", e)))
+                Err(map_api_error(e.as_ref(), || ResyClientError::ApiError(format!("Error fetching venue: {:?}", e))))
             }
         }
     }
+
+    /// Orders slots by booking preference: closest to target_time first, with fill preference as tiebreak.
+    fn _rank_slots(&self, mut slots: Vec<ResySlot>, party_size: u8) -> Vec<ResySlot> {
+        if let Some(fill_preference) = self.config.fill_preference {
+            sort_slots_by_fill(&mut slots, party_size, fill_preference);
+        }
+
+        // sorted before the stable closest-time sort so it only breaks ties between equally close slots
+        if self.config.prefer_abundant_slots {
+            sort_slots_by_abundance(&mut slots);
+        }
+
+        if let Some(target_time) = &self.config.target_time {
+            slots = sort_slots_by_closest_time(slots, target_time);
+        }
+
+        // dining room tables for one are rarely released, so solo diners try the bar first
+        // (an explicit seating preference already filtered the slots, so leave that alone)
+        if party_size == 1 && self.config.seating_preference.is_none() {
+            slots.sort_by_key(|slot| !is_bar_seating(&slot.slot_type));
+        }
+
+        slots
+    }
 }
 
 // UTILS
 
-fn extract_venue_slug(url: &str) -> ResyResult<String> {
-    if let Some(start) = url.find("venues/") {
-        let start = start + "venues/".len();
-        let end = url[start..].find('?').unwrap_or_else(|| url[start..].len());
-        return Ok(url[start..start + end].to_string());
-    }
-    Err(ResyClientError::InvalidInput("invalid resy url".to_string()))
-}
-
-#[derive(Deserialize, Debug)]
-pub(crate) struct ResySlot {
-    pub(crate) id: String,
-    pub(crate) token: String,
-    pub(crate) slot_type: String,
-    pub(crate) start: String,
-    pub(crate) end: String,
-    pub(crate) min_size: u64,
-    pub(crate) max_size: u64,
-    pub(crate) quantity: u64,
-}
-
-fn format_slots(json: Value) -> Vec<ResySlot> {
-    if let Some(slots) = json["results"]["venues"][0]["slots"].as_array() {
-        let summarized: Vec<ResySlot> = slots.iter().filter_map(|slot| {
-
-            let config = slot["config"].as_object()?;
-            let date = slot["date"].as_object()?;
-            let size = slot["size"].as_object()?;
-
-            Some(ResySlot {
-                id: config.get("id")?.as_number()?.to_string(),
-                token: config.get("token")?.as_str()?.to_string(),
-                slot_type: config.get("type")?.as_str()?.to_string(),
-                start: date.get("start")?.as_str()?.to_string(),
-                end: date.get("end")?.as_str()?.to_string(),
-                min_size: size.get("min")?.as_u64()?,
-                max_size: size.get("max")?.as_u64()?,
-                quantity: slot.get("quantity")?.as_u64()?,
+// pops under the lock and releases it straight away, so no worker holds it across an attempt
+fn next_slot(queue: &StdMutex<VecDeque<ResySlot>>) -> Option<ResySlot> {
+    queue.lock().ok().and_then(|mut queue| queue.pop_front())
+}
+
+/// Resolves to the first successful result, dropping the rest. A `Cancelled` result stops every
+/// other future immediately; otherwise the last error is returned once all have failed.
+pub async fn first_ok_unless_cancelled<T, F>(futures: impl IntoIterator<Item = F>) -> ResyResult<T>
+where
+    F: Future<Output = ResyResult<T>>,
+{
+    let mut pending: Vec<_> = futures.into_iter().map(Box::pin).collect();
+    let mut last_error = ResyClientError::InternalError("nothing to run".to_string());
+
+    while !pending.is_empty() {
+        let (result, _, remaining) = select_all(pending).await;
+        match result {
+            Ok(value) => return Ok(value),
+            Err(ResyClientError::Cancelled) => return Err(ResyClientError::Cancelled),
+            Err(e) => last_error = e,
+        }
+        pending = remaining;
+    }
+
+    Err(last_error)
+}
+
+// expired auth gets its own error so callers can prompt a refresh; anything else uses the fallback
+fn map_api_error<F>(error: &(dyn Error + Send + Sync + 'static), fallback: F) -> ResyClientError
+where
+    F: FnOnce() -> ResyClientError,
+{
+    match error.downcast_ref::<ResyAPIError>() {
+        Some(ResyAPIError::AuthExpired(_)) => ResyClientError::AuthExpired,
+        Some(ResyAPIError::Timeout) => ResyClientError::NetworkError("Request timed out".to_string()),
+        _ => fallback(),
+    }
+}
+
+/// Parses a HHMM time string into (hours, minutes), where HH is 00 to 23 and MM is 00 to 59.
+pub fn parse_hhmm(time: &str) -> ResyResult<(u32, u32)> {
+    let invalid = || ResyClientError::InvalidInput("Invalid time format. Please use HHMM format, where HH is 00 to 23 and MM is 00 to 59.".to_string());
+
+    if time.len() != 4 || !time.chars().all(|c| c.is_ascii_digit()) {
+        return Err(invalid());
+    }
+
+    let hours = time[..2].parse::<u32>().map_err(|_| invalid())?;
+    let minutes = time[2..].parse::<u32>().map_err(|_| invalid())?;
+    if hours < 24 && minutes < 60 {
+        Ok((hours, minutes))
+    } else {
+        Err(invalid())
+    }
+}
+
+/// Pulls the venue slug out of a Resy venue link, e.g. `https://resy.com/cities/ny/venues/carbone?date=...`
+/// or the widget form `https://widgets.resy.com/#/venues/carbone?seats=2`. A bare slug is returned as is.
+pub fn extract_venue_slug(url: &str) -> ResyResult<String> {
+    let url = url.trim();
+    let slug = match url.find("venues/") {
+        // the slug is the next path segment, whatever query, fragment or trailing slash follows it
+        Some(start) => url[start + "venues/".len()..].split(['/', '?', '#']).next().unwrap_or_default(),
+        None if url.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') => url,
+        None => "",
+    };
+
+    if slug.is_empty() {
+        return Err(ResyClientError::InvalidInput(format!("invalid resy url '{}': expected a link containing venues/<name>", url)));
+    }
+    Ok(slug.to_string())
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ResySlot {
+    pub id: String,
+    pub token: String,
+    pub slot_type: String,
+    pub start: String,
+    pub end: String,
+    pub min_size: u64,
+    pub max_size: u64,
+    pub quantity: u64,
+    /// Resy id of the venue the slot belongs to, unset in slots saved before it was recorded
+    #[serde(default)]
+    pub venue_id: Option<String>,
+}
+
+/// A venue's id plus the booking metadata Resy returned for it, when it sent any.
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct VenueInfo {
+    pub id: u64,
+    pub slug: String,
+    pub name: Option<String>,
+    pub neighborhood: Option<String>,
+    pub min_party_size: Option<u8>,
+    pub max_party_size: Option<u8>,
+    /// how many days ahead reservations open
+    pub advance_booking_days: Option<u32>,
+    pub responsiveness: Option<String>,
+}
+
+// Resy has named the booking window differently across venue responses
+const ADVANCE_BOOKING_DAYS_KEYS: &[&str] = &["advance_booking_days", "days_in_advance", "booking_window_days"];
+
+/// Reads a `/3/venue` response; `None` if it has no venue id.
+pub fn parse_venue_info(slug: &str, venue: &Value) -> Option<VenueInfo> {
+    let small_number = |value: &Value| value.as_u64().and_then(|n| u8::try_from(n).ok());
+    let advance_booking_days = ADVANCE_BOOKING_DAYS_KEYS.iter()
+        .find_map(|key| venue[*key].as_u64().or_else(|| venue["booking_window"][*key].as_u64()))
+        .or_else(|| venue["booking_window"]["days"].as_u64())
+        .and_then(|days| u32::try_from(days).ok());
+    let responsiveness = match &venue["responsiveness"] {
+        Value::String(text) => Some(text.clone()),
+        Value::Null => None,
+        other => Some(other.to_string()),
+    };
+
+    Some(VenueInfo {
+        id: venue["id"]["resy"].as_u64()?,
+        slug: slug.to_string(),
+        name: venue["name"].as_str().map(str::to_string),
+        neighborhood: venue["location"]["neighborhood"].as_str().map(str::to_string),
+        min_party_size: small_number(&venue["min_party_size"]),
+        max_party_size: small_number(&venue["max_party_size"]),
+        advance_booking_days,
+        responsiveness,
+    })
+}
+
+#[derive(Debug)]
+pub struct PaymentMethod {
+    pub id: String,
+    pub method_type: String,
+    pub last4: String,
+    pub is_default: bool,
+}
+
+fn format_payment_methods(user_data: &Value) -> ResyResult<Vec<PaymentMethod>> {
+    debug!("payment_methods from user response: {}", user_data["payment_methods"]);
+
+    // New accounts come back with the array missing, null or empty until a card is added
+    let payment_methods = match user_data["payment_methods"].as_array() {
+        Some(payment_methods) if !payment_methods.is_empty() => payment_methods,
+        _ => {
+            return Err(ResyClientError::NotFound(
+                "No card on file - add a payment method in the Resy app, then run `load` again".to_string()
+            ));
+        }
+    };
+
+    let methods: Vec<PaymentMethod> = payment_methods.iter().filter_map(|method| {
+        Some(PaymentMethod {
+            id: method.get("id")?.as_i64()?.to_string(),
+            method_type: method.get("type").and_then(Value::as_str).unwrap_or_default().to_string(),
+            last4: method.get("display").and_then(Value::as_str).unwrap_or_default().to_string(),
+            is_default: method.get("is_default").and_then(Value::as_bool).unwrap_or(false),
+        })
+    }).collect();
+
+    if methods.is_empty() {
+        return Err(ResyClientError::ParseError(format!(
+            "Unexpected payment method shape: none of the {} payment method(s) has a numeric id (run with --log-level debug to see the raw response)",
+            payment_methods.len()
+        )));
+    }
+
+    Ok(methods)
+}
+
+/// Picks the method a `Config.payment_preference` names: "default" for the account's default
+/// card, otherwise a method id or a card type such as "amex" (case-insensitive).
+pub fn choose_payment_method<'a>(methods: &'a [PaymentMethod], preference: &str) -> Option<&'a PaymentMethod> {
+    let preference = preference.trim().to_lowercase();
+    if preference == "default" {
+        return methods.iter().find(|method| method.is_default);
+    }
+
+    methods.iter().find(|method| method.id == preference)
+        .or_else(|| methods.iter().find(|method| method.method_type.to_lowercase().contains(&preference)))
+}
+
+#[derive(Debug)]
+pub struct VenueMatch {
+    pub name: String,
+    pub slug: String,
+    pub neighborhood: String,
+    pub venue_id: String,
+}
+
+fn format_venue_matches(json: Value) -> Vec<VenueMatch> {
+    if let Some(hits) = json["search"]["hits"].as_array() {
+        hits.iter().filter_map(|hit| {
+            Some(VenueMatch {
+                name: hit.get("name")?.as_str()?.to_string(),
+                slug: hit.get("url_slug")?.as_str()?.to_string(),
+                neighborhood: hit.get("neighborhood").and_then(Value::as_str).unwrap_or_default().to_string(),
+                venue_id: hit["id"]["resy"].as_u64()?.to_string(),
             })
+        }).collect()
+    } else {
+        Vec::new()
+    }
+}
+
+/// Flattens the slots of every venue in a `/4/find` response, tagging each with its venue's id.
+pub fn format_slots(json: Value, verbose: bool) -> Vec<ResySlot> {
+    let venues = match json["results"]["venues"].as_array() {
+        Some(venues) => venues,
+        None => {
+            if verbose {
+                warn!("Response has no results.venues list: {}", json);
+            }
+            return Vec::new();
+        }
+    };
+
+    let raw_slots: Vec<(Option<String>, &Value)> = venues.iter()
+        .flat_map(|venue| {
+            let venue_id = venue["venue"]["id"]["resy"].as_number().map(|id| id.to_string());
+            venue["slots"].as_array().into_iter().flatten().map(move |slot| (venue_id.clone(), slot))
+        })
+        .collect();
+
+    let summarized: Vec<ResySlot> = raw_slots.iter()
+        .filter_map(|(venue_id, slot)| {
+            let parsed = parse_slot(slot).map(|parsed| ResySlot { venue_id: venue_id.clone(), ..parsed });
+            if parsed.is_none() && verbose {
+                warn!("Dropping slot that could not be parsed: {}", slot);
+            }
+            parsed
         }).collect();
 
-        summarized
+    if verbose {
+        info!("Parsed {} of {} raw slots across {} venues", summarized.len(), raw_slots.len(), venues.len());
     } else {
-        Vec::new()
+        debug!("Parsed {} of {} raw slots across {} venues", summarized.len(), raw_slots.len(), venues.len());
     }
+    summarized
+}
+
+fn parse_slot(slot: &Value) -> Option<ResySlot> {
+    let config = slot["config"].as_object()?;
+    let date = slot["date"].as_object()?;
+    let size = slot["size"].as_object()?;
+
+    Some(ResySlot {
+        id: config.get("id")?.as_number()?.to_string(),
+        token: config.get("token")?.as_str()?.to_string(),
+        slot_type: config.get("type")?.as_str()?.to_string(),
+        start: date.get("start")?.as_str()?.to_string(),
+        end: date.get("end")?.as_str()?.to_string(),
+        min_size: size.get("min")?.as_u64()?,
+        max_size: size.get("max")?.as_u64()?,
+        quantity: slot.get("quantity")?.as_u64()?,
+        venue_id: None,
+    })
+}
+
+// slot starts look like "2024-05-20 19:30:00"
+pub(crate) fn slot_start_time(slot: &ResySlot) -> Option<NaiveTime> {
+    let time = slot.start.get(11..16)?;
+    NaiveTime::parse_from_str(time, "%H:%M").ok()
 }
 
 fn sort_slots_by_closest_time(slots: Vec<ResySlot>, target_time: &str) -> Vec<ResySlot> {
@@ -455,7 +1888,7 @@ fn sort_slots_by_closest_time(slots: Vec<ResySlot>, target_time: &str) -> Vec<Re
     };
 
     let mut slots_with_time: Vec<(ResySlot, NaiveTime)> = slots.into_iter().filter_map(|slot| {
-        NaiveTime::parse_from_str(&slot.start[11..16], "%H:%M").ok().map(|time| (slot, time))
+        slot_start_time(&slot).map(|time| (slot, time))
     }).collect();
 
     slots_with_time.sort_by_key(|(_, time)| {
@@ -469,3 +1902,151 @@ fn sort_slots_by_closest_time(slots: Vec<ResySlot>, target_time: &str) -> Vec<Re
 
     slots_with_time.into_iter().map(|(slot, _)| slot).collect()
 }
+
+fn filter_slots_by_party_size(slots: Vec<ResySlot>, party_size: u8) -> Vec<ResySlot> {
+    let party_size = party_size as u64;
+
+    slots.into_iter().filter(|slot| {
+        let fits = slot.min_size <= party_size && party_size <= slot.max_size;
+        if !fits {
+            debug!("Skipping slot {} ({}): seats {}-{}, party of {}", slot.start, slot.slot_type, slot.min_size, slot.max_size, party_size);
+        }
+        fits
+    }).collect()
+}
+
+/// Converts a wall-clock time to an instant in `timezone`, handling DST transitions explicitly: a time
+/// repeated by the fall-back overlap resolves to its earlier occurrence, and a time skipped by the
+/// spring-forward gap moves forward to the next minute that exists. Both cases log a warning.
+pub fn resolve_local_datetime<T: TimeZone>(timezone: &T, naive: NaiveDateTime) -> Option<DateTime<T>> {
+    match timezone.from_local_datetime(&naive) {
+        LocalResult::Single(datetime) => Some(datetime),
+        LocalResult::Ambiguous(earliest, _) => {
+            warn!("{} happens twice due to a DST change; using the earlier one", naive);
+            Some(earliest)
+        }
+        LocalResult::None => {
+            // DST gaps are at most a couple of hours, so give up well past that
+            let shifted = (1..=180)
+                .map(|minutes| naive + Duration::minutes(minutes))
+                .find_map(|candidate| timezone.from_local_datetime(&candidate).earliest())?;
+            warn!("{} does not exist due to a DST change; using {}", naive, shifted.naive_local());
+            Some(shifted)
+        }
+    }
+}
+
+/// How long to sleep before re-checking the countdown: 60s while far out, ramping through 10s and 1s
+/// down to 200ms in the final seconds, and never past the snipe time itself.
+pub fn next_sleep_duration(remaining: Duration) -> Duration {
+    let step = if remaining > Duration::minutes(2) {
+        Duration::seconds(60)
+    } else if remaining > Duration::seconds(15) {
+        Duration::seconds(10)
+    } else if remaining > Duration::seconds(2) {
+        Duration::seconds(1)
+    } else {
+        Duration::milliseconds(200)
+    };
+
+    step.min(remaining).max(Duration::zero())
+}
+
+// HH:MM:SS, with hours allowed to exceed 24
+fn format_countdown(remaining: Duration) -> String {
+    let total_seconds = remaining.num_seconds().max(0);
+    format!("{:02}:{:02}:{:02}", total_seconds / 3600, (total_seconds % 3600) / 60, total_seconds % 60)
+}
+
+// keeps slots starting within the inclusive [earliest, latest] HHMM window
+pub fn filter_slots_by_window(slots: Vec<ResySlot>, earliest: Option<&str>, latest: Option<&str>) -> Vec<ResySlot> {
+    if earliest.is_none() && latest.is_none() {
+        return slots;
+    }
+
+    let earliest = earliest.and_then(|time| NaiveTime::parse_from_str(time, "%H%M").ok());
+    let latest = latest.and_then(|time| NaiveTime::parse_from_str(time, "%H%M").ok());
+
+    slots.into_iter().filter(|slot| {
+        match slot_start_time(slot) {
+            Some(start) => earliest.is_none_or(|t| start >= t) && latest.is_none_or(|t| start <= t),
+            None => false,
+        }
+    }).collect()
+}
+
+// keeps slots whose type contains the seating preference, or all slots if none match
+fn filter_slots_by_seating(slots: Vec<ResySlot>, seating: &str) -> Vec<ResySlot> {
+    let seating = seating.to_lowercase();
+    let (matching, others): (Vec<ResySlot>, Vec<ResySlot>) = slots.into_iter()
+        .partition(|slot| slot.slot_type.to_lowercase().contains(&seating));
+
+    if matching.is_empty() && !others.is_empty() {
+        warn!("No slots match seating '{}', falling back to all {} slots", seating, others.len());
+        return others;
+    }
+
+    matching
+}
+
+/// Reads slots saved with `venue --json`.
+fn load_slots_file(path: &Path) -> ResyResult<Vec<ResySlot>> {
+    let content = fs::read_to_string(path)
+        .map_err(|e| ResyClientError::InvalidInput(format!("Could not read slots file {}: {}", path.display(), e)))?;
+    let slots: Vec<ResySlot> = serde_json::from_str(&content)
+        .map_err(|e| ResyClientError::ParseError(format!("Invalid slots file {}: {}", path.display(), e)))?;
+
+    if slots.is_empty() {
+        return Err(ResyClientError::InvalidInput(format!("Slots file {} has no slots", path.display())));
+    }
+    Ok(slots)
+}
+
+/// Drops slots repeating an earlier slot's start and seating type, keeping the first.
+fn dedup_slots(slots: Vec<ResySlot>) -> Vec<ResySlot> {
+    let mut seen = HashSet::new();
+    slots.into_iter()
+        .filter(|slot| seen.insert((slot.start.clone(), slot.slot_type.clone())))
+        .collect()
+}
+
+pub fn sort_slots(slots: &mut [ResySlot], sort: SlotSort) {
+    // start is "YYYY-MM-DD HH:MM:SS", so it sorts chronologically as a string
+    match sort {
+        SlotSort::Time => slots.sort_by(|a, b| a.start.cmp(&b.start)),
+        SlotSort::Type => slots.sort_by(|a, b| (&a.slot_type, &a.start).cmp(&(&b.slot_type, &b.start))),
+        SlotSort::Size => slots.sort_by_key(|slot| (slot.min_size, slot.max_size)),
+    }
+}
+
+/// How long from `now` a book token stays valid, from the details response's `date_expires` (UTC) when present.
+/// A token that has already expired gets zero; the default only covers a missing or unreadable expiry.
+pub fn book_token_ttl(book_token: &Value, now: DateTime<Utc>) -> StdDuration {
+    book_token["date_expires"].as_str()
+        .and_then(|expires| NaiveDateTime::parse_from_str(expires, "%Y-%m-%d %H:%M:%S").ok())
+        .map(|expires| (expires.and_utc() - now).to_std().unwrap_or_default())
+        .unwrap_or(StdDuration::from_secs(DEFAULT_BOOK_TOKEN_TTL_SECS))
+}
+
+fn is_bar_seating(slot_type: &str) -> bool {
+    let slot_type = slot_type.to_lowercase();
+    slot_type.contains("bar") || slot_type.contains("counter")
+}
+
+/// Slots with more than one table left come first, since losing a race for them is less likely.
+fn sort_slots_by_abundance(slots: &mut [ResySlot]) {
+    slots.sort_by_key(|slot| slot.quantity <= 1);
+}
+
+// stable sort, so a later sort by time keeps this order among equally close slots
+pub fn sort_slots_by_fill(slots: &mut [ResySlot], party_size: u8, fill_preference: FillPreference) {
+    let party_size = party_size as u64;
+
+    slots.sort_by_key(|slot| {
+        let fills_table = slot.min_size == party_size;
+        match fill_preference {
+            FillPreference::Tight => !fills_table,
+            FillPreference::Loose => fills_table,
+        }
+    });
+}
\ No newline at end of file
@@ -0,0 +1,73 @@
+// stats.rs
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+/// Aggregated results of every snipe fired at one venue with one fire offset, kept in `stats.json` in the data directory (`~/.marksman` by default).
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct VenueStats {
+    pub venue_slug: String,
+    pub fire_offset_ms: i64,
+    pub attempts: u32,
+    pub successes: u32,
+    /// summed over the snipes that got a book token, for the average
+    pub book_token_ms_total: u64,
+    pub book_token_samples: u32,
+}
+
+impl VenueStats {
+    pub fn success_rate(&self) -> f64 {
+        if self.attempts == 0 {
+            0.0
+        } else {
+            self.successes as f64 / self.attempts as f64
+        }
+    }
+
+    pub fn average_book_token_ms(&self) -> Option<u64> {
+        (self.book_token_samples > 0).then(|| self.book_token_ms_total / self.book_token_samples as u64)
+    }
+}
+
+pub fn get_stats_path(data_dir: &Path) -> PathBuf {
+    data_dir.join("stats.json")
+}
+
+/// Reads every venue's stats; a missing file means no snipes yet.
+pub fn read_all(data_dir: &Path) -> Result<Vec<VenueStats>> {
+    let path = get_stats_path(data_dir);
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let content = fs::read_to_string(&path).context("Failed to read stats file")?;
+    serde_json::from_str(&content).context("Failed to parse stats file")
+}
+
+/// Counts one fired snipe towards its venue and fire offset.
+pub fn record(data_dir: &Path, venue_slug: &str, fire_offset_ms: i64, success: bool, time_to_book_token: Option<Duration>) -> Result<()> {
+    let mut all_stats = read_all(data_dir)?;
+    let index = match all_stats.iter().position(|stats| stats.venue_slug == venue_slug && stats.fire_offset_ms == fire_offset_ms) {
+        Some(index) => index,
+        None => {
+            all_stats.push(VenueStats { venue_slug: venue_slug.to_string(), fire_offset_ms, ..VenueStats::default() });
+            all_stats.len() - 1
+        }
+    };
+
+    let stats = &mut all_stats[index];
+    stats.attempts += 1;
+    if success {
+        stats.successes += 1;
+    }
+    if let Some(latency) = time_to_book_token {
+        stats.book_token_ms_total += latency.as_millis() as u64;
+        stats.book_token_samples += 1;
+    }
+
+    fs::create_dir_all(data_dir).context("Failed to create stats directory")?;
+    let content = serde_json::to_string_pretty(&all_stats).context("Failed to serialize stats")?;
+    fs::write(get_stats_path(data_dir), content).context("Failed to write stats file")
+}
@@ -2,22 +2,199 @@
 use prettytable::{row, Table};
 use prettytable::row::Row;
 use prettytable::cell::Cell;
-use crate::resy_client::ResySlot;
+use chrono::{DateTime, Local};
+use std::io::{self, Write};
+use crate::history::HistoryEntry;
+use crate::stats::VenueStats;
+use crate::resy_client::{slot_start_time, ResySlot, VenueInfo};
+
+/// Prints slots with a friendly start time; `raw` adds the API's start/end timestamps and
+/// `color` tints each row by seating type. `plain` prints borderless tab-separated rows instead,
+/// for piping into other tools.
+pub fn print_table(slots: &[ResySlot], raw: bool, color: bool, plain: bool) {
+    if plain {
+        print_plain(slots, raw);
+        return;
+    }
 
-pub fn print_table(slots: &[ResySlot]) {
     let mut table = Table::new();
-    table.add_row(row!["type", "start", "end", "min_sz", "max_sz", "qty", "id", "token"]);
+    if raw {
+        table.add_row(row!["type", "time", "start", "end", "min_sz", "max_sz", "qty", "id", "token"]);
+    } else {
+        table.add_row(row!["type", "time", "min_sz", "max_sz", "qty", "id", "token"]);
+    }
 
     for slot in slots {
-        table.add_row(Row::new(vec![
+        let mut cells = vec![
             Cell::new(&slot.slot_type),
-            Cell::new(&slot.start),
-            Cell::new(&slot.end),
+            Cell::new(&friendly_time(slot)),
+        ];
+        if raw {
+            cells.push(Cell::new(&slot.start));
+            cells.push(Cell::new(&slot.end));
+        }
+        cells.extend([
             Cell::new(&slot.min_size.to_string()),
             Cell::new(&slot.max_size.to_string()),
             Cell::new(&slot.quantity.to_string()),
             Cell::new(&slot.id),
             Cell::new(&slot.token),
+        ]);
+        if let Some(style) = color_for_slot_type(&slot.slot_type).filter(|_| color) {
+            cells = cells.into_iter().map(|cell| cell.style_spec(style)).collect();
+        }
+        table.add_row(Row::new(cells));
+    }
+
+    table.printstd();
+}
+
+/// The venue's booking metadata as display lines, skipping whatever Resy didn't send.
+pub fn venue_info_lines(info: &VenueInfo) -> Vec<String> {
+    let mut lines = Vec::new();
+    match (info.min_party_size, info.max_party_size) {
+        (Some(min), Some(max)) => lines.push(format!("Party sizes: {}-{}", min, max)),
+        (Some(min), None) => lines.push(format!("Party sizes: {}+", min)),
+        (None, Some(max)) => lines.push(format!("Party sizes: up to {}", max)),
+        (None, None) => {}
+    }
+    if let Some(days) = info.advance_booking_days {
+        lines.push(format!("Reservations open {} days in advance", days));
+    }
+    if let Some(responsiveness) = &info.responsiveness {
+        lines.push(format!("Responsiveness: {}", responsiveness));
+    }
+    lines
+}
+
+/// Writes slots as CSV with a header row and the same columns as the `--raw` table, quoting
+/// fields (e.g. a seating type like "Bar, Counter") that need it.
+pub fn write_csv(slots: &[ResySlot], w: &mut impl Write) -> io::Result<()> {
+    writeln!(w, "type,time,start,end,min_sz,max_sz,qty,id,token,venue_id")?;
+    for slot in slots {
+        let fields = [
+            slot.slot_type.clone(),
+            friendly_time(slot),
+            slot.start.clone(),
+            slot.end.clone(),
+            slot.min_size.to_string(),
+            slot.max_size.to_string(),
+            slot.quantity.to_string(),
+            slot.id.clone(),
+            slot.token.clone(),
+            slot.venue_id.clone().unwrap_or_default(),
+        ];
+        let line: Vec<String> = fields.iter().map(|field| csv_field(field)).collect();
+        writeln!(w, "{}", line.join(","))?;
+    }
+    Ok(())
+}
+
+/// Quotes a CSV field if it contains a comma, quote or newline.
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+fn print_plain(slots: &[ResySlot], raw: bool) {
+    let header: &[&str] = if raw {
+        &["type", "time", "start", "end", "min_sz", "max_sz", "qty", "id", "token"]
+    } else {
+        &["type", "time", "min_sz", "max_sz", "qty", "id", "token"]
+    };
+    println!("{}", header.join("\t"));
+
+    for slot in slots {
+        let mut fields = vec![slot.slot_type.clone(), friendly_time(slot)];
+        if raw {
+            fields.push(slot.start.clone());
+            fields.push(slot.end.clone());
+        }
+        fields.extend([
+            slot.min_size.to_string(),
+            slot.max_size.to_string(),
+            slot.quantity.to_string(),
+            slot.id.clone(),
+            slot.token.clone(),
+        ]);
+        println!("{}", fields.join("\t"));
+    }
+}
+
+/// Renders the slot start as e.g. "7:30 PM", falling back to the raw string if it can't be parsed.
+fn friendly_time(slot: &ResySlot) -> String {
+    match slot_start_time(slot) {
+        Some(time) => time.format("%-I:%M %p").to_string(),
+        None => slot.start.clone(),
+    }
+}
+
+/// Maps a seating type to a prettytable style spec (green dining room, yellow bar, cyan patio).
+pub fn color_for_slot_type(slot_type: &str) -> Option<&'static str> {
+    let slot_type = slot_type.to_lowercase();
+    if slot_type.contains("dining") {
+        Some("Fg")
+    } else if slot_type.contains("bar") || slot_type.contains("counter") {
+        Some("Fy")
+    } else if slot_type.contains("patio") || slot_type.contains("outdoor") {
+        Some("Fc")
+    } else {
+        None
+    }
+}
+
+pub fn print_history(entries: &[HistoryEntry]) {
+    let mut table = Table::new();
+    table.add_row(row!["recorded", "venue", "date", "time", "party", "outcome"]);
+
+    for entry in entries {
+        let recorded_at = DateTime::parse_from_rfc3339(&entry.recorded_at)
+            .map(|at| at.with_timezone(&Local).format("%Y-%m-%d %H:%M").to_string())
+            .unwrap_or_else(|_| entry.recorded_at.clone());
+        let outcome = if entry.success {
+            "booked".to_string()
+        } else {
+            format!("failed: {}", entry.reason.as_deref().unwrap_or("unknown"))
+        };
+
+        table.add_row(Row::new(vec![
+            Cell::new(&recorded_at),
+            Cell::new(&entry.venue_slug),
+            Cell::new(&entry.date),
+            Cell::new(entry.time.as_deref().unwrap_or("-")),
+            Cell::new(&entry.party_size.to_string()),
+            Cell::new(&outcome),
+        ]));
+    }
+
+    table.printstd();
+}
+
+/// Prints per-venue snipe stats, best success rate first.
+pub fn print_stats(entries: &[VenueStats]) {
+    let mut entries: Vec<&VenueStats> = entries.iter().collect();
+    entries.sort_by(|a, b| {
+        b.success_rate().total_cmp(&a.success_rate()).then(b.attempts.cmp(&a.attempts))
+    });
+
+    let mut table = Table::new();
+    table.add_row(row!["venue", "fire offset", "attempts", "booked", "success", "avg token"]);
+
+    for stats in entries {
+        let average = stats.average_book_token_ms()
+            .map(|ms| format!("{}ms", ms))
+            .unwrap_or_else(|| "-".to_string());
+
+        table.add_row(Row::new(vec![
+            Cell::new(&stats.venue_slug),
+            Cell::new(&format!("{}ms", stats.fire_offset_ms)),
+            Cell::new(&stats.attempts.to_string()),
+            Cell::new(&stats.successes.to_string()),
+            Cell::new(&format!("{:.0}%", stats.success_rate() * 100.0)),
+            Cell::new(&average),
         ]));
     }
 
@@ -0,0 +1,42 @@
+use std::time::Duration;
+use marksman::resy_client::{first_ok_unless_cancelled, ResyClientError, ResyResult};
+use tokio::time::sleep;
+
+async fn after(ms: u64, result: ResyResult<&'static str>) -> ResyResult<&'static str> {
+    sleep(Duration::from_millis(ms)).await;
+    result
+}
+
+#[tokio::test]
+async fn declined_confirmation_stops_the_other_accounts() {
+    // the main account is cancelled at its confirmation while a secondary account would still book
+    let snipes = vec![
+        after(10, Err(ResyClientError::Cancelled)),
+        after(50, Ok("booked from secondary")),
+    ];
+
+    assert!(matches!(first_ok_unless_cancelled(snipes).await, Err(ResyClientError::Cancelled)));
+}
+
+#[tokio::test]
+async fn failed_account_leaves_the_others_running() {
+    let snipes = vec![
+        after(10, Err(ResyClientError::BookingError("sold out".to_string()))),
+        after(50, Ok("booked from secondary")),
+    ];
+
+    assert_eq!(first_ok_unless_cancelled(snipes).await.unwrap(), "booked from secondary");
+}
+
+#[tokio::test]
+async fn last_error_is_returned_when_every_account_fails() {
+    let snipes = vec![
+        after(10, Err(ResyClientError::BookingError("first".to_string()))),
+        after(20, Err(ResyClientError::BookingError("second".to_string()))),
+    ];
+
+    match first_ok_unless_cancelled(snipes).await {
+        Err(ResyClientError::BookingError(reason)) => assert_eq!(reason, "second"),
+        other => panic!("expected the last booking error, got {:?}", other),
+    }
+}
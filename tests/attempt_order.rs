@@ -0,0 +1,73 @@
+use std::sync::Arc;
+use chrono::{TimeZone, Utc};
+use marksman::clock::SimulatedClock;
+use marksman::{ResyClient, ResyClientError, SnipeOptions};
+use serde_json::{json, Value};
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+fn slot(token: &str, start: &str) -> Value {
+    json!({
+        "config": { "id": 1, "token": token, "type": "Dining Room" },
+        "date": { "start": start, "end": "2030-01-08 23:00:00" },
+        "size": { "min": 2, "max": 2 },
+        "quantity": 1,
+    })
+}
+
+#[tokio::test]
+async fn limited_attempts_take_slots_in_rank_order() {
+    let server = MockServer::start().await;
+    // listed worst first, so rank order and response order disagree
+    Mock::given(method("GET"))
+        .and(path("/4/find"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "results": { "venues": [{ "slots": [
+            slot("2100", "2030-01-08 21:00:00"),
+            slot("2000", "2030-01-08 20:00:00"),
+            slot("1930", "2030-01-08 19:30:00"),
+        ] }] } })))
+        .mount(&server)
+        .await;
+    // every slot is already gone, so each one gets attempted
+    Mock::given(method("POST"))
+        .and(path("/3/details"))
+        .respond_with(ResponseTemplate::new(410))
+        .mount(&server)
+        .await;
+
+    let mut client = ResyClient::new();
+    client.config.api_key = "test-key".to_string();
+    client.config.auth_token = "test-token".to_string();
+    client.config.venue_id = "1234".to_string();
+    client.config.payment_id = "42".to_string();
+    client.config.date = "2030-01-08".to_string();
+    client.config.party_size = 2;
+    client.config.target_time = Some("1930".to_string());
+    client.config.venue_timezone = Some("UTC".to_string());
+    client.config.max_retries = 0;
+    client.config.discovery_retries = 0;
+    client.config.max_concurrent_attempts = 1;
+    // enough jitter that random delays would reorder the attempts if they raced for the turn
+    client.config.request_jitter_ms = 30;
+    client.config.api_base_url = Some(server.uri());
+    client.load_config(client.config.clone());
+    client.set_clock(Arc::new(SimulatedClock::starting_at(Utc.with_ymd_and_hms(2030, 1, 1, 9, 55, 0).unwrap())));
+    client.set_data_dir(std::env::temp_dir().join(format!("marksman-attempt-order-{}", std::process::id())));
+
+    let result = client.run_sniper("1000", "2030-01-01", &SnipeOptions::default()).await;
+
+    match result {
+        Err(ResyClientError::AllSlotsFailed(failures)) => {
+            let attempted: Vec<&str> = failures.iter().map(|(slot, _)| slot.token.as_str()).collect();
+            assert_eq!(attempted, ["1930", "2000", "2100"]);
+        }
+        other => panic!("expected every slot to fail, got {:?}", other),
+    }
+
+    let requests = server.received_requests().await.unwrap();
+    let details: Vec<String> = requests.iter()
+        .filter(|request| request.url.path() == "/3/details")
+        .map(|request| request.body_json::<Value>().unwrap()["config_id"].as_str().unwrap().to_string())
+        .collect();
+    assert_eq!(details, ["1930", "2000", "2100"]);
+}
@@ -0,0 +1,189 @@
+use std::sync::Arc;
+use std::time::Duration as StdDuration;
+use chrono::{DateTime, Duration, Local, TimeZone, Utc};
+use marksman::clock::{Clock, SimulatedClock};
+use marksman::config;
+use marksman::resy_client::book_token_ttl;
+use marksman::{ResyClient, ResyClientError, SnipeOptions};
+use serde_json::json;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+#[test]
+fn simulated_clock_starts_at_the_given_time() {
+    let start = Utc.with_ymd_and_hms(2024, 5, 20, 9, 59, 30).unwrap();
+    let clock = SimulatedClock::starting_at(start);
+
+    let now = clock.now_utc();
+    assert!(now >= start && now - start < Duration::seconds(1));
+}
+
+#[tokio::test]
+async fn sleeping_on_a_simulated_clock_jumps_ahead() {
+    let start = Utc.with_ymd_and_hms(2024, 5, 20, 9, 59, 30).unwrap();
+    let clock = SimulatedClock::starting_at(start);
+
+    let before = std::time::Instant::now();
+    clock.sleep(StdDuration::from_secs(60)).await;
+
+    assert!(clock.now_utc() - start >= Duration::seconds(60));
+    assert!(before.elapsed() < StdDuration::from_secs(1));
+}
+
+#[test]
+fn simulated_clock_advances_in_real_time() {
+    let clock = SimulatedClock::starting_at(Utc.with_ymd_and_hms(2024, 5, 20, 9, 59, 30).unwrap());
+    let before = clock.now_utc();
+    std::thread::sleep(std::time::Duration::from_millis(20));
+    assert!(clock.now_utc() - before >= Duration::milliseconds(20));
+}
+
+/// Always reads the same instant.
+#[derive(Debug)]
+struct FixedClock(DateTime<Utc>);
+
+impl Clock for FixedClock {
+    fn now_local(&self) -> DateTime<Local> {
+        self.0.with_timezone(&Local)
+    }
+
+    fn now_utc(&self) -> DateTime<Utc> {
+        self.0
+    }
+}
+
+fn fixed_at(year: i32, month: u32, day: u32, hour: u32) -> FixedClock {
+    FixedClock(Utc.with_ymd_and_hms(year, month, day, hour, 0, 0).unwrap())
+}
+
+fn client_at(clock: FixedClock) -> ResyClient {
+    let mut client = ResyClient::new();
+    client.config.api_key = "test-key".to_string();
+    client.config.auth_token = "test-token".to_string();
+    client.config.venue_id = "1234".to_string();
+    client.config.payment_id = "42".to_string();
+    client.config.date = "2030-01-08".to_string();
+    client.config.party_size = 2;
+    client.config.venue_timezone = Some("UTC".to_string());
+    client.set_clock(Arc::new(clock));
+    // a failed snipe is still recorded, so keep it out of the real history
+    client.set_data_dir(std::env::temp_dir().join(format!("marksman-clock-{}", std::process::id())));
+    client
+}
+
+#[test]
+fn default_date_is_a_week_out() {
+    assert_eq!(config::default_date(&fixed_at(2030, 2, 25, 12)), "2030-03-04");
+}
+
+#[tokio::test]
+async fn snipe_before_now_is_rejected() {
+    let mut client = client_at(fixed_at(2030, 1, 1, 12));
+
+    match client.run_dry_sniper("1100", "2030-01-01", &SnipeOptions::default()).await {
+        Err(ResyClientError::InvalidInput(message)) => assert!(message.contains("in the past"), "{}", message),
+        other => panic!("expected the snipe to be rejected, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn snipe_past_the_horizon_is_rejected() {
+    let mut client = client_at(fixed_at(2030, 1, 1, 12));
+
+    match client.run_dry_sniper("1100", "2030-03-01", &SnipeOptions::default()).await {
+        Err(ResyClientError::InvalidInput(message)) => assert!(message.contains("days out"), "{}", message),
+        other => panic!("expected the snipe to be rejected, got {:?}", other),
+    }
+}
+
+#[test]
+fn book_token_ttl_is_measured_from_the_given_now() {
+    let book_token = json!({ "value": "book-token", "date_expires": "2030-01-01 12:05:00" });
+
+    assert_eq!(book_token_ttl(&book_token, fixed_at(2030, 1, 1, 12).now_utc()), StdDuration::from_secs(300));
+}
+
+#[test]
+fn book_token_ttl_is_zero_once_expired() {
+    let book_token = json!({ "value": "book-token", "date_expires": "2030-01-01 11:55:00" });
+
+    assert_eq!(book_token_ttl(&book_token, fixed_at(2030, 1, 1, 12).now_utc()), StdDuration::ZERO);
+}
+
+#[test]
+fn book_token_ttl_defaults_without_a_readable_expiry() {
+    let now = fixed_at(2030, 1, 1, 12).now_utc();
+
+    assert_eq!(book_token_ttl(&json!({ "value": "book-token" }), now), StdDuration::from_secs(60));
+    assert_eq!(book_token_ttl(&json!({ "value": "book-token", "date_expires": "soon" }), now), StdDuration::from_secs(60));
+}
+
+#[tokio::test]
+async fn malformed_stored_time_fails_before_waiting_for_a_snipe() {
+    let mut client = client_at(fixed_at(2030, 1, 1, 12));
+    client.config.target_time = Some("2575".to_string());
+
+    // the fixed clock never reaches the snipe time, so only an up-front check can return
+    let result = tokio::time::timeout(StdDuration::from_secs(5), client.run_sniper("1100", "2030-01-02", &SnipeOptions::default())).await
+        .expect("the snipe waited instead of failing");
+
+    match result {
+        Err(ResyClientError::InvalidInput(message)) => assert!(message.contains("target_time"), "{}", message),
+        other => panic!("expected the config to be rejected, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn malformed_stored_time_fails_before_waiting_for_a_dry_run() {
+    let mut client = client_at(fixed_at(2030, 1, 1, 12));
+    client.config.target_time = Some("2575".to_string());
+
+    let result = tokio::time::timeout(StdDuration::from_secs(5), client.run_dry_sniper("1100", "2030-01-02", &SnipeOptions::default())).await
+        .expect("the dry run waited instead of failing");
+
+    match result {
+        Err(ResyClientError::InvalidInput(message)) => assert!(message.contains("target_time"), "{}", message),
+        other => panic!("expected the config to be rejected, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn simulated_snipe_fires_without_waiting_for_the_drop() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/4/find"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "results": { "venues": [{ "slots": [{
+            "config": { "id": 1, "token": "slot-token", "type": "Dining Room" },
+            "date": { "start": "2030-01-08 19:30:00", "end": "2030-01-08 21:00:00" },
+            "size": { "min": 2, "max": 2 },
+            "quantity": 1,
+        }] }] } })))
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/3/details"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "book_token": { "value": "book-token" } })))
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/3/book"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "resy_token": "resy-token" })))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let mut client = client_at(fixed_at(2030, 1, 1, 12));
+    client.config.max_retries = 0;
+    client.config.api_base_url = Some(server.uri());
+    client.load_config(client.config.clone());
+    // five minutes before the 10:00 drop
+    client.set_clock(Arc::new(SimulatedClock::starting_at(Utc.with_ymd_and_hms(2030, 1, 1, 9, 55, 0).unwrap())));
+
+    let options = SnipeOptions::default();
+    let snipe = client.run_sniper("1000", "2030-01-01", &options);
+    let outcome = tokio::time::timeout(StdDuration::from_secs(10), snipe).await
+        .expect("the simulated snipe waited for the real drop")
+        .unwrap();
+
+    assert_eq!(outcome.resy_token, "resy-token");
+}
@@ -0,0 +1,51 @@
+use std::time::Duration;
+use marksman::{Config, ResyClient, ResyClientError, WatchOptions};
+
+fn lookup_config() -> Config {
+    Config {
+        api_key: "test-key".to_string(),
+        auth_token: "test-token".to_string(),
+        venue_id: "1234".to_string(),
+        date: "2030-01-08".to_string(),
+        party_size: 2,
+        payment_id: String::new(),
+        ..Config::default()
+    }
+}
+
+#[test]
+fn slot_lookup_does_not_need_a_payment_method() {
+    let config = lookup_config();
+
+    assert!(config.validate(), "{:?}", config.validation_errors());
+}
+
+#[test]
+fn booking_needs_a_payment_method() {
+    let config = lookup_config();
+
+    assert!(!config.validate_for_booking());
+    assert_eq!(config.booking_validation_errors(), ["payment_id is not set"]);
+}
+
+#[test]
+fn booking_config_with_a_payment_method_is_valid() {
+    let config = Config { payment_id: "42".to_string(), ..lookup_config() };
+
+    assert!(config.validate_for_booking(), "{:?}", config.booking_validation_errors());
+}
+
+#[tokio::test]
+async fn auto_booking_watch_needs_a_payment_method_up_front() {
+    let mut client = ResyClient::new();
+    client.load_config(Config { api_base_url: Some("http://127.0.0.1:9".to_string()), ..lookup_config() });
+    let options = WatchOptions { interval: Duration::from_secs(3600), max_duration: None, auto_book: true };
+
+    let result = tokio::time::timeout(Duration::from_secs(5), client.watch(&options)).await
+        .expect("watch polled instead of failing on the missing payment method");
+
+    match result {
+        Err(ResyClientError::InvalidInput(reason)) => assert!(reason.contains("payment_id"), "{}", reason),
+        other => panic!("expected an incomplete config error, got {:?}", other),
+    }
+}
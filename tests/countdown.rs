@@ -0,0 +1,31 @@
+use chrono::Duration;
+use marksman::resy_client::next_sleep_duration;
+
+#[test]
+fn sleep_ramps_down_as_snipe_time_nears() {
+    assert_eq!(next_sleep_duration(Duration::minutes(10)), Duration::seconds(60));
+    assert_eq!(next_sleep_duration(Duration::seconds(90)), Duration::seconds(10));
+    assert_eq!(next_sleep_duration(Duration::seconds(10)), Duration::seconds(1));
+    assert_eq!(next_sleep_duration(Duration::seconds(2)), Duration::milliseconds(200));
+}
+
+#[test]
+fn sleep_never_passes_snipe_time() {
+    assert_eq!(next_sleep_duration(Duration::milliseconds(150)), Duration::milliseconds(150));
+    assert_eq!(next_sleep_duration(Duration::zero()), Duration::zero());
+    assert_eq!(next_sleep_duration(Duration::milliseconds(-5)), Duration::zero());
+}
+
+#[test]
+fn final_sleep_lands_within_100ms_of_target() {
+    let mut remaining = Duration::minutes(5) + Duration::milliseconds(437);
+    let mut last_step = Duration::zero();
+
+    while remaining > Duration::zero() {
+        last_step = next_sleep_duration(remaining);
+        remaining -= last_step;
+    }
+
+    assert_eq!(remaining, Duration::zero());
+    assert!(last_step <= Duration::milliseconds(200));
+}
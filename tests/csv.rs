@@ -0,0 +1,103 @@
+use marksman::resy_client::ResySlot;
+use marksman::view_utils::write_csv;
+
+fn sample_slot() -> ResySlot {
+    ResySlot {
+        id: "12345".to_string(),
+        token: "rgs://resy/1/2/3/2024-05-20/2024-05-20/19:30:00/2/Bar, Counter".to_string(),
+        slot_type: "Bar, Counter".to_string(),
+        start: "2024-05-20 19:30:00".to_string(),
+        end: "2024-05-20 21:00:00".to_string(),
+        min_size: 1,
+        max_size: 2,
+        quantity: 3,
+        venue_id: Some("1234".to_string()),
+    }
+}
+
+/// Splits one CSV line, honouring quoted fields and doubled quotes.
+fn split_csv_line(line: &str) -> Vec<String> {
+    let mut fields = vec![String::new()];
+    let mut in_quotes = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if in_quotes && chars.peek() == Some(&'"') => {
+                chars.next();
+                fields.last_mut().unwrap().push('"');
+            }
+            '"' => in_quotes = !in_quotes,
+            ',' if !in_quotes => fields.push(String::new()),
+            c => fields.last_mut().unwrap().push(c),
+        }
+    }
+    fields
+}
+
+#[test]
+fn header_row() {
+    let mut out = Vec::new();
+    write_csv(&[], &mut out).unwrap();
+    assert_eq!(String::from_utf8(out).unwrap(), "type,time,start,end,min_sz,max_sz,qty,id,token,venue_id\n");
+}
+
+#[test]
+fn slot_round_trips() {
+    let slot = sample_slot();
+    let mut out = Vec::new();
+    write_csv(std::slice::from_ref(&slot), &mut out).unwrap();
+
+    let text = String::from_utf8(out).unwrap();
+    let lines: Vec<&str> = text.lines().collect();
+    assert_eq!(lines.len(), 2);
+    assert!(lines[1].starts_with("\"Bar, Counter\",7:30 PM,"));
+
+    let fields = split_csv_line(lines[1]);
+    assert_eq!(fields, vec![
+        slot.slot_type,
+        "7:30 PM".to_string(),
+        slot.start,
+        slot.end,
+        slot.min_size.to_string(),
+        slot.max_size.to_string(),
+        slot.quantity.to_string(),
+        slot.id,
+        slot.token,
+        "1234".to_string(),
+    ]);
+}
+
+#[test]
+fn slots_keep_their_venue() {
+    let first = sample_slot();
+    let second = ResySlot { id: "67890".to_string(), venue_id: Some("5678".to_string()), ..sample_slot() };
+    let unknown = ResySlot { venue_id: None, ..sample_slot() };
+    let mut out = Vec::new();
+    write_csv(&[first, second, unknown], &mut out).unwrap();
+
+    let text = String::from_utf8(out).unwrap();
+    let rows: Vec<(String, String)> = text.lines().skip(1)
+        .map(|line| {
+            let fields = split_csv_line(line);
+            (fields[7].clone(), fields[9].clone())
+        })
+        .collect();
+    assert_eq!(rows, vec![
+        ("12345".to_string(), "1234".to_string()),
+        ("67890".to_string(), "5678".to_string()),
+        ("12345".to_string(), String::new()),
+    ]);
+}
+
+#[test]
+fn quotes_are_doubled() {
+    let mut slot = sample_slot();
+    slot.slot_type = "Chef's \"Table\"".to_string();
+    let mut out = Vec::new();
+    write_csv(&[slot], &mut out).unwrap();
+
+    let text = String::from_utf8(out).unwrap();
+    let row = text.lines().nth(1).unwrap();
+    assert!(row.starts_with("\"Chef's \"\"Table\"\"\","));
+    assert_eq!(split_csv_line(row)[0], "Chef's \"Table\"");
+}
@@ -0,0 +1,59 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use marksman::config::get_data_dir;
+use marksman::history::{self, HistoryEntry};
+use marksman::stats;
+
+/// A fresh directory per test, so parallel tests don't see each other's files.
+fn scratch_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("marksman-data-dir-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    dir
+}
+
+#[test]
+fn data_dir_sits_next_to_the_config() {
+    assert_eq!(get_data_dir(Path::new("/srv/marksman/work.config")), Path::new("/srv/marksman/.marksman"));
+}
+
+#[test]
+fn default_config_keeps_data_in_dot_marksman() {
+    assert_eq!(get_data_dir(Path::new("/home/diner/.marksman.config")), Path::new("/home/diner/.marksman"));
+}
+
+#[test]
+fn history_is_read_back_from_the_same_data_dir() {
+    let dir = scratch_dir("history");
+    let entry = HistoryEntry {
+        recorded_at: "2030-01-01T10:00:00+00:00".to_string(),
+        venue_slug: "carbone".to_string(),
+        date: "2030-01-08".to_string(),
+        time: Some("2030-01-08 19:30:00".to_string()),
+        party_size: 2,
+        success: true,
+        resy_token: Some("resy-token".to_string()),
+        reason: None,
+    };
+
+    history::append(&dir, &entry).unwrap();
+
+    let entries = history::read_all(&dir).unwrap();
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0].venue_slug, "carbone");
+    assert!(history::read_all(&scratch_dir("history-other")).unwrap().is_empty());
+}
+
+#[test]
+fn stats_are_read_back_from_the_same_data_dir() {
+    let dir = scratch_dir("stats");
+
+    stats::record(&dir, "carbone", -50, true, Some(Duration::from_millis(120))).unwrap();
+    stats::record(&dir, "carbone", -50, false, None).unwrap();
+
+    let all_stats = stats::read_all(&dir).unwrap();
+    assert_eq!(all_stats.len(), 1);
+    assert_eq!((all_stats[0].attempts, all_stats[0].successes), (2, 1));
+    assert_eq!(all_stats[0].average_book_token_ms(), Some(120));
+    assert!(stats::read_all(&scratch_dir("stats-other")).unwrap().is_empty());
+}
@@ -0,0 +1,32 @@
+use chrono::{NaiveDate, NaiveDateTime, TimeZone, Utc};
+use chrono_tz::America::New_York;
+use marksman::resy_client::resolve_local_datetime;
+
+fn naive(date: (i32, u32, u32), time: (u32, u32)) -> NaiveDateTime {
+    NaiveDate::from_ymd_opt(date.0, date.1, date.2).unwrap()
+        .and_hms_opt(time.0, time.1, 0).unwrap()
+}
+
+#[test]
+fn ordinary_time_resolves_directly() {
+    let resolved = resolve_local_datetime(&New_York, naive((2024, 5, 20), (0, 0))).unwrap();
+
+    assert_eq!(resolved.with_timezone(&Utc), Utc.with_ymd_and_hms(2024, 5, 20, 4, 0, 0).unwrap());
+}
+
+#[test]
+fn fall_back_overlap_uses_the_earlier_instant() {
+    // 01:30 happens first in EDT (UTC-4), then again in EST (UTC-5)
+    let resolved = resolve_local_datetime(&New_York, naive((2024, 11, 3), (1, 30))).unwrap();
+
+    assert_eq!(resolved.with_timezone(&Utc), Utc.with_ymd_and_hms(2024, 11, 3, 5, 30, 0).unwrap());
+}
+
+#[test]
+fn spring_forward_gap_moves_to_the_next_valid_minute() {
+    // clocks jump from 02:00 EST straight to 03:00 EDT
+    let resolved = resolve_local_datetime(&New_York, naive((2024, 3, 10), (2, 30))).unwrap();
+
+    assert_eq!(resolved.naive_local(), naive((2024, 3, 10), (3, 0)));
+    assert_eq!(resolved.with_timezone(&Utc), Utc.with_ymd_and_hms(2024, 3, 10, 7, 0, 0).unwrap());
+}
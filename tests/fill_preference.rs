@@ -0,0 +1,36 @@
+use marksman::config::FillPreference;
+use marksman::resy_client::{sort_slots_by_fill, ResySlot};
+
+fn slot(id: &str, min_size: u64, max_size: u64) -> ResySlot {
+    ResySlot {
+        id: id.to_string(),
+        token: format!("token-{}", id),
+        slot_type: "Dining Room".to_string(),
+        start: "2024-05-20 19:30:00".to_string(),
+        end: "2024-05-20 21:00:00".to_string(),
+        min_size,
+        max_size,
+        quantity: 1,
+        venue_id: None,
+    }
+}
+
+fn ids(slots: &[ResySlot]) -> Vec<&str> {
+    slots.iter().map(|slot| slot.id.as_str()).collect()
+}
+
+#[test]
+fn tight_prefers_a_table_the_party_fills() {
+    let mut slots = vec![slot("oversized", 2, 6), slot("filled", 4, 4)];
+    sort_slots_by_fill(&mut slots, 4, FillPreference::Tight);
+
+    assert_eq!(ids(&slots), ["filled", "oversized"]);
+}
+
+#[test]
+fn loose_prefers_a_table_with_room_to_spare() {
+    let mut slots = vec![slot("filled", 4, 4), slot("oversized", 2, 6)];
+    sort_slots_by_fill(&mut slots, 4, FillPreference::Loose);
+
+    assert_eq!(ids(&slots), ["oversized", "filled"]);
+}
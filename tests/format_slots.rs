@@ -0,0 +1,31 @@
+use marksman::resy_client::format_slots;
+use serde_json::{json, Value};
+
+fn slot(token: &str, start: &str) -> Value {
+    json!({
+        "config": { "id": 1, "token": token, "type": "Dining Room" },
+        "date": { "start": start, "end": "2024-05-20 23:00:00" },
+        "size": { "min": 2, "max": 4 },
+        "quantity": 1,
+    })
+}
+
+#[test]
+fn slots_from_every_venue_are_kept_with_their_venue() {
+    let json = json!({ "results": { "venues": [
+        { "venue": { "id": { "resy": 1234 } }, "slots": [slot("a-1930", "2024-05-20 19:30:00"), slot("a-2000", "2024-05-20 20:00:00")] },
+        { "venue": { "id": { "resy": 5678 } }, "slots": [slot("b-2100", "2024-05-20 21:00:00")] },
+    ] } });
+
+    let slots = format_slots(json, false);
+
+    let attributed: Vec<(&str, Option<&str>)> = slots.iter()
+        .map(|slot| (slot.token.as_str(), slot.venue_id.as_deref()))
+        .collect();
+    assert_eq!(attributed, [("a-1930", Some("1234")), ("a-2000", Some("1234")), ("b-2100", Some("5678"))]);
+}
+
+#[test]
+fn no_venues_means_no_slots() {
+    assert!(format_slots(json!({ "results": { "venues": [] } }), false).is_empty());
+}
@@ -0,0 +1,30 @@
+use std::time::Duration;
+use marksman::{Config, ResyAPIGateway, ResyClient};
+
+#[test]
+fn update_auth_keeps_the_http_client() {
+    let mut client = ResyClient::new();
+    let before = client.api_gateway().clone();
+
+    client.update_auth("new-key".to_string(), "new-token".to_string());
+
+    assert!(client.api_gateway().shares_client_with(&before));
+}
+
+#[test]
+fn load_config_keeps_the_http_client() {
+    let mut client = ResyClient::new();
+    let before = client.api_gateway().clone();
+
+    client.load_config(Config { api_key: "new-key".to_string(), ..Config::default() });
+
+    assert!(client.api_gateway().shares_client_with(&before));
+}
+
+#[test]
+fn changing_timeouts_builds_a_new_http_client() {
+    let gateway = ResyAPIGateway::new();
+    let rebuilt = gateway.clone().with_timeouts(Duration::from_secs(1), Duration::from_secs(1));
+
+    assert!(!rebuilt.shares_client_with(&gateway));
+}
@@ -0,0 +1,29 @@
+use std::path::Path;
+use marksman::config::keyring_user;
+
+#[test]
+fn each_config_file_gets_its_own_entry() {
+    let work = keyring_user(Path::new("/srv/marksman/work.config"), "api_key");
+    let personal = keyring_user(Path::new("/srv/marksman/personal.config"), "api_key");
+
+    assert_ne!(work, personal);
+    assert_eq!(work, "/srv/marksman/work.config:api_key");
+}
+
+#[test]
+fn fields_of_one_config_get_their_own_entries() {
+    let config = Path::new("/srv/marksman/work.config");
+
+    assert_ne!(keyring_user(config, "api_key"), keyring_user(config, "auth_token"));
+    assert_ne!(keyring_user(config, "accounts.0.api_key"), keyring_user(config, "accounts.1.api_key"));
+}
+
+#[test]
+fn relative_and_absolute_paths_share_an_entry() {
+    let cwd = std::env::current_dir().unwrap();
+
+    assert_eq!(
+        keyring_user(Path::new("work.config"), "api_key"),
+        keyring_user(&cwd.join("work.config"), "api_key"),
+    );
+}
@@ -0,0 +1,28 @@
+use marksman::resy_client::{parse_hhmm, ResyClientError};
+
+#[test]
+fn valid_times_are_split_into_hours_and_minutes() {
+    assert_eq!(parse_hhmm("0000").unwrap(), (0, 0));
+    assert_eq!(parse_hhmm("1930").unwrap(), (19, 30));
+    assert_eq!(parse_hhmm("2359").unwrap(), (23, 59));
+}
+
+#[test]
+fn hour_24_is_rejected() {
+    assert!(matches!(parse_hhmm("2400"), Err(ResyClientError::InvalidInput(_))));
+}
+
+#[test]
+fn minute_60_is_rejected() {
+    assert!(matches!(parse_hhmm("0060"), Err(ResyClientError::InvalidInput(_))));
+}
+
+#[test]
+fn non_digits_are_rejected() {
+    assert!(matches!(parse_hhmm("abcd"), Err(ResyClientError::InvalidInput(_))));
+}
+
+#[test]
+fn empty_string_is_rejected() {
+    assert!(matches!(parse_hhmm(""), Err(ResyClientError::InvalidInput(_))));
+}
@@ -0,0 +1,35 @@
+use marksman::resy_client::{choose_payment_method, PaymentMethod};
+
+fn method(id: &str, method_type: &str, is_default: bool) -> PaymentMethod {
+    PaymentMethod {
+        id: id.to_string(),
+        method_type: method_type.to_string(),
+        last4: "1234".to_string(),
+        is_default,
+    }
+}
+
+fn methods() -> Vec<PaymentMethod> {
+    vec![method("11", "Visa", false), method("22", "Amex", true), method("33", "Mastercard", false)]
+}
+
+#[test]
+fn default_picks_the_default_card() {
+    assert_eq!(choose_payment_method(&methods(), "default").unwrap().id, "22");
+}
+
+#[test]
+fn card_type_matches_case_insensitively() {
+    assert_eq!(choose_payment_method(&methods(), "AMEX").unwrap().id, "22");
+    assert_eq!(choose_payment_method(&methods(), "master").unwrap().id, "33");
+}
+
+#[test]
+fn id_matches_exactly() {
+    assert_eq!(choose_payment_method(&methods(), "11").unwrap().id, "11");
+}
+
+#[test]
+fn unknown_preference_matches_nothing() {
+    assert!(choose_payment_method(&methods(), "discover").is_none());
+}
@@ -0,0 +1,161 @@
+use std::sync::{Arc, Mutex};
+use std::time::Duration as StdDuration;
+use chrono::{DateTime, Duration, Local, TimeZone, Utc};
+use futures::future::BoxFuture;
+use marksman::clock::Clock;
+use marksman::resy_client::ResyResult;
+use marksman::{BookingOutcome, ResyClient, SnipeOptions};
+use serde_json::{json, Value};
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, Request, Respond, ResponseTemplate};
+
+/// A clock that only moves when the sniper sleeps on it, so a drop replays instantly and every
+/// request lands at an exact simulated instant.
+#[derive(Debug, Clone)]
+struct ReplayClock(Arc<Mutex<DateTime<Utc>>>);
+
+impl ReplayClock {
+    fn starting_at(start: DateTime<Utc>) -> Self {
+        ReplayClock(Arc::new(Mutex::new(start)))
+    }
+}
+
+impl Clock for ReplayClock {
+    fn now_local(&self) -> DateTime<Local> {
+        self.now_utc().with_timezone(&Local)
+    }
+
+    fn now_utc(&self) -> DateTime<Utc> {
+        *self.0.lock().unwrap()
+    }
+
+    fn sleep(&self, duration: StdDuration) -> BoxFuture<'static, ()> {
+        *self.0.lock().unwrap() += Duration::from_std(duration).unwrap();
+        Box::pin(tokio::task::yield_now())
+    }
+}
+
+/// `/4/find` responses captured around a drop, each served from its offset after the drop until the next one.
+struct RecordedDrop {
+    clock: ReplayClock,
+    drop_at: DateTime<Utc>,
+    responses: Vec<(Duration, Value)>,
+}
+
+impl Respond for RecordedDrop {
+    fn respond(&self, _: &Request) -> ResponseTemplate {
+        let elapsed = self.clock.now_utc() - self.drop_at;
+        let body = self.responses.iter()
+            .take_while(|(offset, _)| *offset <= elapsed)
+            .last()
+            .map_or_else(no_slots, |(_, body)| body.clone());
+        ResponseTemplate::new(200).set_body_json(body)
+    }
+}
+
+/// Notes the simulated instant of every `/3/book` call.
+struct BookingRecorder {
+    clock: ReplayClock,
+    booked_at: Arc<Mutex<Vec<DateTime<Utc>>>>,
+}
+
+impl Respond for BookingRecorder {
+    fn respond(&self, _: &Request) -> ResponseTemplate {
+        self.booked_at.lock().unwrap().push(self.clock.now_utc());
+        ResponseTemplate::new(200).set_body_json(json!({ "resy_token": "resy-token" }))
+    }
+}
+
+fn no_slots() -> Value {
+    json!({ "results": { "venues": [{ "slots": [] }] } })
+}
+
+fn released_slots() -> Value {
+    json!({ "results": { "venues": [{ "slots": [{
+        "config": { "id": 1, "token": "slot-token", "type": "Dining Room" },
+        "date": { "start": "2030-01-08 19:30:00", "end": "2030-01-08 21:00:00" },
+        "size": { "min": 2, "max": 2 },
+        "quantity": 1,
+    }] }] } })
+}
+
+fn drop_time() -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2030, 1, 1, 10, 0, 0).unwrap()
+}
+
+fn fresh_book_token() -> Value {
+    json!({ "value": "book-token" })
+}
+
+/// Replays `responses` against a snipe for the 10:00 UTC drop, with every details call returning
+/// `book_token`, and returns the simulated booking times.
+async fn replay(responses: Vec<(Duration, Value)>, book_token: Value) -> (ResyResult<BookingOutcome>, Vec<DateTime<Utc>>) {
+    let clock = ReplayClock::starting_at(drop_time() - Duration::minutes(5));
+    let booked_at = Arc::new(Mutex::new(Vec::new()));
+
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/4/find"))
+        .respond_with(RecordedDrop { clock: clock.clone(), drop_at: drop_time(), responses })
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/3/details"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "book_token": book_token })))
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/3/book"))
+        .respond_with(BookingRecorder { clock: clock.clone(), booked_at: Arc::clone(&booked_at) })
+        .mount(&server)
+        .await;
+
+    let mut client = ResyClient::new();
+    client.config.api_key = "test-key".to_string();
+    client.config.auth_token = "test-token".to_string();
+    client.config.venue_id = "1234".to_string();
+    client.config.venue_slug = "test-venue".to_string();
+    client.config.payment_id = "42".to_string();
+    client.config.date = "2030-01-08".to_string();
+    client.config.party_size = 2;
+    client.config.venue_timezone = Some("UTC".to_string());
+    client.config.max_retries = 0;
+    client.config.api_base_url = Some(server.uri());
+    client.load_config(client.config.clone());
+    client.set_clock(Arc::new(clock));
+    client.set_data_dir(std::env::temp_dir().join(format!("marksman-replay-{}", std::process::id())));
+
+    let result = client.run_sniper("1000", "2030-01-01", &SnipeOptions { book_first: true, ..SnipeOptions::default() }).await;
+    let booked_at = booked_at.lock().unwrap().clone();
+    (result, booked_at)
+}
+
+#[tokio::test]
+async fn booking_lands_when_the_recorded_slots_appear() {
+    // inventory lagged the drop: two empty polls, then the slot shows up 400ms in
+    let (result, booked_at) = replay(vec![
+        (Duration::zero(), no_slots()),
+        (Duration::milliseconds(400), released_slots()),
+    ], fresh_book_token()).await;
+
+    let outcome = result.unwrap();
+    assert_eq!(outcome.resy_token, "resy-token");
+    assert_eq!(booked_at, vec![drop_time() + Duration::milliseconds(400)]);
+}
+
+#[tokio::test]
+async fn booking_lands_at_the_drop_when_slots_are_already_out() {
+    let (result, booked_at) = replay(vec![(Duration::zero(), released_slots())], fresh_book_token()).await;
+
+    assert!(result.is_ok(), "{:?}", result);
+    assert_eq!(booked_at, vec![drop_time()]);
+}
+
+#[tokio::test]
+async fn slot_with_an_already_expired_book_token_is_skipped() {
+    let expired = json!({ "value": "book-token", "date_expires": "2030-01-01 09:59:00" });
+    let (result, booked_at) = replay(vec![(Duration::zero(), released_slots())], expired).await;
+
+    assert!(result.is_err(), "{:?}", result);
+    assert!(booked_at.is_empty(), "booked with a dead token at {:?}", booked_at);
+}
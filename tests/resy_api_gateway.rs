@@ -0,0 +1,195 @@
+use std::time::{Duration, Instant};
+use chrono::{TimeZone, Utc};
+use marksman::resy_api_gateway::{parse_retry_after, BookBodyFormat, ResyAPIError, ResyAPIGateway};
+use serde_json::json;
+use wiremock::matchers::{body_json, body_string, header, method, path, query_param};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+fn gateway(server: &MockServer) -> ResyAPIGateway {
+    ResyAPIGateway::from_auth("test-key".to_string(), "test-token".to_string())
+        .with_base_url(server.uri())
+        .with_max_retries(0)
+}
+
+#[tokio::test]
+async fn find_reservation_sends_query_and_auth_headers() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/4/find"))
+        .and(query_param("day", "2024-05-20"))
+        .and(query_param("party_size", "2"))
+        .and(query_param("venue_id", "1234"))
+        .and(query_param("time_filter", "19:30"))
+        .and(header("authorization", "ResyAPI api_key=\"test-key\""))
+        .and(header("x-resy-auth-token", "test-token"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "results": { "venues": [] } })))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let json = gateway(&server).find_reservation("1234", "2024-05-20", 2, Some("1930")).await.unwrap();
+
+    assert_eq!(json, json!({ "results": { "venues": [] } }));
+}
+
+#[tokio::test]
+async fn book_reservation_sends_form_body() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/3/book"))
+        .and(header("content-type", "application/x-www-form-urlencoded"))
+        .and(body_string("book_token=abc%2F123%3D&struct_payment_method={\"id\":42}"))
+        .respond_with(ResponseTemplate::new(201).set_body_json(json!({ "resy_token": "booked" })))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let json = gateway(&server).book_reservation("abc/123=", "42").await.unwrap();
+
+    assert_eq!(json["resy_token"], "booked");
+}
+
+#[tokio::test]
+async fn book_reservation_sends_json_body_when_configured() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/3/book"))
+        .and(header("content-type", "application/json"))
+        .and(body_json(json!({ "book_token": "abc/123=", "struct_payment_method": { "id": 42 } })))
+        .respond_with(ResponseTemplate::new(201).set_body_json(json!({ "resy_token": "booked" })))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let mut gateway = gateway(&server);
+    gateway.set_book_body_format(BookBodyFormat::Json);
+    let json = gateway.book_reservation("abc/123=", "42").await.unwrap();
+
+    assert_eq!(json["resy_token"], "booked");
+}
+
+#[tokio::test]
+async fn rejected_auth_token_is_reported_as_expired() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/2/user"))
+        .respond_with(ResponseTemplate::new(419))
+        .mount(&server)
+        .await;
+
+    let error = gateway(&server).get_user().await.unwrap_err();
+
+    assert!(matches!(error.downcast_ref::<ResyAPIError>(), Some(ResyAPIError::AuthExpired(_))));
+}
+
+#[tokio::test]
+async fn json_and_form_requests_send_identical_headers() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/2/user"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({})))
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/3/book"))
+        .respond_with(ResponseTemplate::new(201).set_body_json(json!({ "resy_token": "booked" })))
+        .mount(&server)
+        .await;
+
+    let gateway = gateway(&server);
+    gateway.get_user().await.unwrap();
+    gateway.book_reservation("abc", "42").await.unwrap();
+
+    let requests = server.received_requests().await.unwrap();
+    let headers_without_body_type = |index: usize| {
+        let mut headers: Vec<(String, String)> = requests[index].headers.iter()
+            .filter(|(name, _)| !matches!(name.as_str(), "content-type" | "content-length"))
+            .map(|(name, value)| (name.to_string(), value.to_str().unwrap().to_string()))
+            .collect();
+        headers.sort();
+        headers
+    };
+
+    assert_eq!(requests.len(), 2);
+    assert_eq!(requests[0].headers["content-type"], "application/json");
+    assert_eq!(requests[1].headers["content-type"], "application/x-www-form-urlencoded");
+    assert_eq!(headers_without_body_type(0), headers_without_body_type(1));
+}
+
+#[tokio::test]
+async fn non_json_success_body_is_reported_with_a_snippet() {
+    let server = MockServer::start().await;
+    let page = format!("<html><body>Down for maintenance{}</body></html>", " ".repeat(10) + &"x".repeat(500));
+    Mock::given(method("GET"))
+        .and(path("/2/user"))
+        .respond_with(ResponseTemplate::new(200).set_body_string(page))
+        .mount(&server)
+        .await;
+
+    let error = gateway(&server).get_user().await.unwrap_err();
+
+    match error.downcast_ref::<ResyAPIError>() {
+        Some(ResyAPIError::InvalidResponse { status, snippet }) => {
+            assert_eq!(status.as_u16(), 200);
+            assert!(snippet.starts_with("<html><body>Down for maintenance x"));
+            assert!(snippet.ends_with("..."));
+            assert!(snippet.chars().count() < 250);
+        }
+        other => panic!("expected InvalidResponse, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn rate_limited_request_waits_for_retry_after() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/3/book"))
+        .respond_with(ResponseTemplate::new(429).insert_header("retry-after", "1"))
+        .up_to_n_times(1)
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/3/book"))
+        .respond_with(ResponseTemplate::new(201).set_body_json(json!({ "resy_token": "booked" })))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let started = Instant::now();
+    let json = gateway(&server).with_max_retries(1).book_reservation("abc", "42").await.unwrap();
+
+    assert_eq!(json["resy_token"], "booked");
+    assert!(started.elapsed() >= Duration::from_secs(1));
+}
+
+#[test]
+fn retry_after_accepts_seconds_and_dates() {
+    let now = Utc.with_ymd_and_hms(2015, 10, 21, 7, 27, 50).unwrap();
+
+    assert_eq!(parse_retry_after("2", now), Some(Duration::from_secs(2)));
+    assert_eq!(parse_retry_after("Wed, 21 Oct 2015 07:28:00 GMT", now), Some(Duration::from_secs(10)));
+    assert_eq!(parse_retry_after("Wed, 21 Oct 2015 07:27:00 GMT", now), Some(Duration::ZERO));
+    assert_eq!(parse_retry_after("3600", now), Some(Duration::from_secs(30)));
+    assert_eq!(parse_retry_after("soon", now), None);
+}
+
+#[tokio::test]
+async fn login_sends_credentials_without_the_old_token() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/3/auth/password"))
+        .and(header("content-type", "application/x-www-form-urlencoded"))
+        .and(header("authorization", "ResyAPI api_key=\"test-key\""))
+        .and(body_string("email=me%40example.com&password=p%26ss&otp=123456"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "token": "fresh-token" })))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let json = gateway(&server).login("me@example.com", "p&ss", Some("123456")).await.unwrap();
+
+    assert_eq!(json["token"], "fresh-token");
+    let requests = server.received_requests().await.unwrap();
+    assert!(!requests[0].headers.contains_key("x-resy-auth-token"));
+}
@@ -0,0 +1,41 @@
+use marksman::resy_client::{filter_slots_by_window, ResySlot};
+
+fn slot_at(start: &str) -> ResySlot {
+    ResySlot {
+        id: start.to_string(),
+        token: format!("token-{}", start),
+        slot_type: "Dining Room".to_string(),
+        start: format!("2024-05-20 {}:00", start),
+        end: "2024-05-20 23:00:00".to_string(),
+        min_size: 2,
+        max_size: 4,
+        quantity: 1,
+        venue_id: None,
+    }
+}
+
+fn starts(slots: &[ResySlot]) -> Vec<&str> {
+    slots.iter().map(|slot| slot.id.as_str()).collect()
+}
+
+#[test]
+fn slot_on_the_earliest_boundary_is_kept() {
+    let slots = filter_slots_by_window(vec![slot_at("18:59"), slot_at("19:00"), slot_at("20:00")], Some("1900"), Some("2100"));
+
+    assert_eq!(starts(&slots), ["19:00", "20:00"]);
+}
+
+#[test]
+fn slot_on_the_latest_boundary_is_kept() {
+    let slots = filter_slots_by_window(vec![slot_at("20:00"), slot_at("21:00"), slot_at("21:01")], Some("1900"), Some("2100"));
+
+    assert_eq!(starts(&slots), ["20:00", "21:00"]);
+}
+
+#[test]
+fn one_sided_windows_are_inclusive() {
+    let slots = vec![slot_at("18:59"), slot_at("19:00"), slot_at("21:00"), slot_at("21:01")];
+
+    assert_eq!(starts(&filter_slots_by_window(slots.clone(), Some("1900"), None)), ["19:00", "21:00", "21:01"]);
+    assert_eq!(starts(&filter_slots_by_window(slots, None, Some("2100"))), ["18:59", "19:00", "21:00"]);
+}
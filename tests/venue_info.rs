@@ -0,0 +1,40 @@
+use marksman::resy_client::parse_venue_info;
+use serde_json::json;
+
+#[test]
+fn booking_metadata_is_parsed() {
+    let venue = json!({
+        "id": { "resy": 6194 },
+        "name": "Carbone",
+        "location": { "neighborhood": "Greenwich Village" },
+        "min_party_size": 1,
+        "max_party_size": 6,
+        "advance_booking_days": 30,
+        "responsiveness": "high",
+    });
+
+    let info = parse_venue_info("carbone", &venue).unwrap();
+
+    assert_eq!(info.id, 6194);
+    assert_eq!(info.slug, "carbone");
+    assert_eq!(info.name.as_deref(), Some("Carbone"));
+    assert_eq!(info.neighborhood.as_deref(), Some("Greenwich Village"));
+    assert_eq!((info.min_party_size, info.max_party_size), (Some(1), Some(6)));
+    assert_eq!(info.advance_booking_days, Some(30));
+    assert_eq!(info.responsiveness.as_deref(), Some("high"));
+}
+
+#[test]
+fn missing_metadata_is_left_empty() {
+    let info = parse_venue_info("carbone", &json!({ "id": { "resy": 6194 } })).unwrap();
+
+    assert_eq!(info.name, None);
+    assert_eq!(info.max_party_size, None);
+    assert_eq!(info.advance_booking_days, None);
+    assert_eq!(info.responsiveness, None);
+}
+
+#[test]
+fn venue_without_id_is_rejected() {
+    assert!(parse_venue_info("carbone", &json!({ "name": "Carbone" })).is_none());
+}
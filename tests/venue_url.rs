@@ -0,0 +1,64 @@
+use marksman::resy_api_gateway::ResyAPIGateway;
+use marksman::resy_client::{extract_venue_slug, ResyClientError};
+use serde_json::json;
+use wiremock::matchers::{method, path, query_param};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+#[test]
+fn city_venue_url() {
+    assert_eq!(extract_venue_slug("https://resy.com/cities/ny/venues/carbone").unwrap(), "carbone");
+}
+
+#[test]
+fn query_string_is_dropped() {
+    assert_eq!(extract_venue_slug("https://resy.com/cities/ny/venues/carbone?date=2024-05-20&seats=2").unwrap(), "carbone");
+}
+
+#[test]
+fn trailing_slash_is_dropped() {
+    assert_eq!(extract_venue_slug("https://resy.com/cities/ny/venues/carbone/").unwrap(), "carbone");
+}
+
+#[test]
+fn fragment_is_dropped() {
+    assert_eq!(extract_venue_slug("https://resy.com/cities/ny/venues/carbone#menu").unwrap(), "carbone");
+}
+
+#[test]
+fn widget_url() {
+    assert_eq!(extract_venue_slug("https://widgets.resy.com/#/venues/carbone?seats=2&date=2024-05-20").unwrap(), "carbone");
+}
+
+#[test]
+fn bare_slug() {
+    assert_eq!(extract_venue_slug(" carbone ").unwrap(), "carbone");
+}
+
+#[test]
+fn unparseable_input_is_rejected() {
+    for url in ["", "https://resy.com/cities/ny", "https://resy.com/cities/ny/venues/", "https://resy.com/cities/ny/venues/?date=2024-05-20"] {
+        assert!(matches!(extract_venue_slug(url), Err(ResyClientError::InvalidInput(_))), "{:?} should be rejected", url);
+    }
+}
+
+#[tokio::test]
+async fn venue_lookup_uses_the_given_location() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/3/venue"))
+        .and(query_param("url_slug", "bestia"))
+        .and(query_param("location", "los-angeles-ca"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "id": { "resy": 1234 } })))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let gateway = ResyAPIGateway::from_auth("test-key".to_string(), "test-token".to_string())
+        .with_base_url(server.uri())
+        .with_max_retries(0);
+    let slug = extract_venue_slug("https://resy.com/cities/la/venues/bestia").unwrap();
+
+    let json = gateway.get_venue(&slug, "los-angeles-ca").await.unwrap();
+
+    assert_eq!(json["id"]["resy"], 1234);
+}